@@ -0,0 +1,75 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use programming_bitcoin_rs::{
+    verify_ecdsa_batch, verify_schnorr_batch, Message, PrivateKey, PublicKey, SchnorrSignature,
+    Signature, XOnlyPublicKey,
+};
+
+fn schnorr_items(size: usize) -> Vec<(Vec<u8>, SchnorrSignature, XOnlyPublicKey)> {
+    (0..size)
+        .map(|i| {
+            let mut bytes = [1u8; 32];
+            bytes[..8].copy_from_slice(&(i as u64).to_be_bytes());
+            let private_key = PrivateKey::new(bytes).unwrap();
+            let message = bytes.to_vec();
+            let signature = private_key.sign_schnorr(&message);
+            let public_key = PublicKey::from_private_key(private_key).x_only_public_key();
+            (message, signature, public_key)
+        })
+        .collect()
+}
+
+fn ecdsa_items(size: usize) -> Vec<(Message, Signature, PublicKey)> {
+    (0..size)
+        .map(|i| {
+            let mut bytes = [1u8; 32];
+            bytes[..8].copy_from_slice(&(i as u64).to_be_bytes());
+            let private_key = PrivateKey::new(bytes).unwrap();
+            let message = Message::new(bytes);
+            let signature = private_key.sign(&message);
+            (message, signature, PublicKey::from_private_key(private_key))
+        })
+        .collect()
+}
+
+fn bench_schnorr(c: &mut Criterion) {
+    let mut group = c.benchmark_group("schnorr verification");
+    for size in [16, 64, 256] {
+        let items = schnorr_items(size);
+        let batch: Vec<_> = items.iter().map(|(m, s, p)| (&m[..], s, p)).collect();
+
+        group.bench_with_input(BenchmarkId::new("one by one", size), &batch, |b, batch| {
+            b.iter(|| {
+                batch
+                    .iter()
+                    .all(|(message, signature, public_key)| public_key.verify(message, signature))
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batch", size), &batch, |b, batch| {
+            b.iter(|| verify_schnorr_batch(batch))
+        });
+    }
+    group.finish();
+}
+
+fn bench_ecdsa(c: &mut Criterion) {
+    let mut group = c.benchmark_group("ecdsa verification");
+    for size in [16, 64, 256] {
+        let items = ecdsa_items(size);
+        let batch: Vec<_> = items.iter().map(|(m, s, p)| (m, s, p)).collect();
+
+        group.bench_with_input(BenchmarkId::new("one by one", size), &batch, |b, batch| {
+            b.iter(|| {
+                batch
+                    .iter()
+                    .all(|(message, signature, public_key)| public_key.verify(message, signature))
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("batch", size), &batch, |b, batch| {
+            b.iter(|| verify_ecdsa_batch(batch))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_schnorr, bench_ecdsa);
+criterion_main!(benches);
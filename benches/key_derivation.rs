@@ -0,0 +1,24 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use programming_bitcoin_rs::{PrivateKey, PublicKey};
+
+fn bench_key_derivation(c: &mut Criterion) {
+    let secrets: Vec<[u8; 32]> = (0..100u64)
+        .map(|i| {
+            let mut bytes = [7u8; 32];
+            bytes[24..].copy_from_slice(&i.to_be_bytes());
+            bytes
+        })
+        .collect();
+
+    c.bench_function("public key derivation", |b| {
+        b.iter(|| {
+            secrets
+                .iter()
+                .map(|secret| PublicKey::from_private_key(PrivateKey::new(*secret).unwrap()))
+                .collect::<Vec<_>>()
+        })
+    });
+}
+
+criterion_group!(benches, bench_key_derivation);
+criterion_main!(benches);
@@ -1,4 +1,4 @@
-use programming_bitcoin_rs::{Address, Chain, Encoding, PrivateKey, PublicKey};
+use programming_bitcoin_rs::{Address, Chain, Encoding, Message, PrivateKey, PublicKey};
 
 extern crate programming_bitcoin_rs;
 
@@ -6,8 +6,16 @@ fn main() {
     let private_key = PrivateKey::new([
         1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
         1, 1,
-    ]);
+    ])
+    .unwrap();
+    let message = Message::new([7u8; 32]);
+    let signature = private_key.sign(&message);
+
     let public_key = PublicKey::from_private_key(private_key);
     let address = Address::new(&public_key, Chain::TestNet, Encoding::Bech32);
     println!("The address is: {}", address);
+    println!(
+        "The signature is valid: {}",
+        public_key.verify(&message, &signature)
+    );
 }
@@ -1,39 +1,161 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use crate::{
-    hash::{hash160, hash256},
+    base58::{self, Base58Error},
+    hash::hash160,
     public_key::PublicKey,
-    serializer::{CanSerialize, PublicKeyCompressedSerializer, PublicKeyUncompressedSerializer},
+    schnorr::XOnlyPublicKey,
+    serializer::{
+        CanSerialize, PublicKeyCompressedSerializer, PublicKeyUncompressedSerializer,
+        ScriptSerializer,
+    },
+    transaction::Script,
 };
 
-#[derive(Clone)]
+/// A Bitcoin network. Signet and regtest use the base58 version bytes of testnet, so their
+/// base58 addresses and WIF keys parse as `TestNet`, and signet segwit addresses do too. Use
+/// [`Address::require_chain`] to say which of them was meant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Chain {
     TestNet,
     MainNet,
+    Signet,
+    Regtest,
 }
 
+/// Prefixes that identify a network in encoded addresses and keys.
+#[derive(Debug, PartialEq, Eq)]
+pub struct NetworkParameters {
+    /// Version byte of P2PKH addresses.
+    pub pubkey_hash_prefix: u8,
+    /// Version byte of P2SH addresses.
+    pub script_hash_prefix: u8,
+    /// Version byte of private keys in Wallet Import Format.
+    pub wif_prefix: u8,
+    /// Version bytes of BIP 32 extended public keys.
+    pub extended_public_key_prefix: [u8; 4],
+    /// Version bytes of BIP 32 extended private keys.
+    pub extended_private_key_prefix: [u8; 4],
+    /// Human readable part of segwit addresses.
+    pub hrp: &'static str,
+}
+
+const MAINNET_PARAMETERS: NetworkParameters = NetworkParameters {
+    pubkey_hash_prefix: 0x00,
+    script_hash_prefix: 0x05,
+    wif_prefix: 0x80,
+    extended_public_key_prefix: [0x04, 0x88, 0xb2, 0x1e],
+    extended_private_key_prefix: [0x04, 0x88, 0xad, 0xe4],
+    hrp: "bc",
+};
+
+const TESTNET_PARAMETERS: NetworkParameters = NetworkParameters {
+    pubkey_hash_prefix: 0x6f,
+    script_hash_prefix: 0xc4,
+    wif_prefix: 0xef,
+    extended_public_key_prefix: [0x04, 0x35, 0x87, 0xcf],
+    extended_private_key_prefix: [0x04, 0x35, 0x83, 0x94],
+    hrp: "tb",
+};
+
+const REGTEST_PARAMETERS: NetworkParameters = NetworkParameters {
+    hrp: "bcrt",
+    ..TESTNET_PARAMETERS
+};
+
 pub enum Encoding {
     CompressedBase58,
     UncompressedBase58,
     Bech32,
+    /// P2WPKH nested in a P2SH output, as in BIP 49.
+    P2shP2wpkh,
+    /// P2TR output whose internal key is the public key, with the merkle root of its script
+    /// tree or `None` for key path spends only, as in BIP 86.
+    Taproot {
+        merkle_root: Option<[u8; 32]>,
+    },
+}
+
+/// What the output script of an address pays to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    /// Hash160 of a public key (P2PKH).
+    PubkeyHash([u8; 20]),
+    /// Hash160 of a redeem script (P2SH).
+    ScriptHash([u8; 20]),
+    /// Segwit output of version 0 to 16.
+    WitnessProgram { version: u8, program: Vec<u8> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    chain: Chain,
+    payload: Payload,
 }
 
+/// Errors found while parsing an address.
 #[derive(Debug, PartialEq, Eq)]
-pub struct Address(String);
+pub enum AddressError {
+    Base58(Base58Error),
+    /// The base58check payload is not a version byte followed by a 20 byte hash.
+    InvalidBase58Length(usize),
+    UnknownVersionByte(u8),
+    /// The bech32 string is longer than 90 characters or has less than 6 checksum characters.
+    InvalidBech32Length(usize),
+    MixedCase,
+    InvalidCharacter {
+        character: char,
+        position: usize,
+    },
+    UnknownHumanReadablePart(String),
+    InvalidChecksum,
+    /// Bech32 used for witness version 1 or above, or Bech32m for version 0 (BIP 350).
+    WrongChecksumVariant,
+    MissingWitnessVersion,
+    InvalidWitnessVersion(u8),
+    InvalidPadding,
+    InvalidWitnessProgramLength(usize),
+    WrongChain(Chain),
+    /// P2SH redeem scripts are pushed on the stack, so they can't be longer than 520 bytes.
+    RedeemScriptTooLong(usize),
+}
+
+const MAX_REDEEM_SCRIPT_LENGTH: usize = 520;
+
+const BECH32_ALPHABET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+const BECH32M_CONSTANT: u32 = 0x2bc830a3;
 
 impl Chain {
-    fn code(self) -> u8 {
+    /// Every chain, with `TestNet` before `Signet` and `Regtest` which share its prefixes.
+    const ALL: [Chain; 4] = [
+        Chain::MainNet,
+        Chain::TestNet,
+        Chain::Signet,
+        Chain::Regtest,
+    ];
+
+    pub fn parameters(self) -> &'static NetworkParameters {
         match self {
-            Chain::TestNet => 0x6f,
-            Chain::MainNet => 0x00,
+            Chain::MainNet => &MAINNET_PARAMETERS,
+            Chain::TestNet | Chain::Signet => &TESTNET_PARAMETERS,
+            Chain::Regtest => &REGTEST_PARAMETERS,
         }
     }
 
-    fn hrp(self) -> [u8; 2] {
-        match self {
-            Chain::TestNet => b"tb".to_owned(),
-            Chain::MainNet => b"bc".to_owned(),
-        }
+    /// First chain, in the order of [`Chain::ALL`], whose parameters match `predicate`.
+    fn find(predicate: impl Fn(&NetworkParameters) -> bool) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|chain| predicate(chain.parameters()))
+    }
+
+    pub(crate) fn from_wif_prefix(prefix: u8) -> Option<Self> {
+        Self::find(|parameters| parameters.wif_prefix == prefix)
+    }
+
+    fn from_hrp(hrp: &str) -> Option<Self> {
+        Self::find(|parameters| parameters.hrp == hrp)
     }
 }
 
@@ -51,38 +173,215 @@ impl Address {
             Encoding::Bech32 => {
                 let public_key_bytes = PublicKeyCompressedSerializer::serialize(public_key);
                 let bytes = hash160(&public_key_bytes);
-                Address(Self::encode_bech32(&bytes, chain))
+                Self {
+                    chain,
+                    payload: Payload::WitnessProgram {
+                        version: 0,
+                        program: bytes.to_vec(),
+                    },
+                }
+            }
+            Encoding::P2shP2wpkh => {
+                let public_key_bytes = PublicKeyCompressedSerializer::serialize(public_key);
+                let redeem_script = Script::witness_program(0, &hash160(&public_key_bytes));
+                Self::from_serialized_redeem_script(
+                    &ScriptSerializer::serialize_commands(&redeem_script),
+                    chain,
+                )
             }
+            Encoding::Taproot { merkle_root } => Self::p2tr(
+                &XOnlyPublicKey::from_public_key(public_key),
+                merkle_root.as_ref(),
+                chain,
+            ),
         }
     }
 
-    fn from_serialized_public_key_base58_check(data: &[u8], chain: Chain) -> Self {
-        let hash = {
-            let mut hash = vec![chain.code()];
-            hash.extend_from_slice(&hash160(data));
-            hash
+    /// P2TR address paying to the output key obtained by tweaking `internal_key` with the merkle
+    /// root of the script tree (BIP 341).
+    pub fn p2tr(
+        internal_key: &XOnlyPublicKey,
+        merkle_root: Option<&[u8; 32]>,
+        chain: Chain,
+    ) -> Self {
+        let output_key = internal_key.tap_tweak(merkle_root);
+        Self {
+            chain,
+            payload: Payload::WitnessProgram {
+                version: 1,
+                program: output_key.to_bytes().to_vec(),
+            },
+        }
+    }
+
+    /// P2SH address of a redeem script, for instance a [`Script::multisig`].
+    pub fn p2sh(redeem_script: &Script, chain: Chain) -> Result<Self, AddressError> {
+        let redeem_script = ScriptSerializer::serialize_commands(redeem_script);
+        if redeem_script.len() > MAX_REDEEM_SCRIPT_LENGTH {
+            return Err(AddressError::RedeemScriptTooLong(redeem_script.len()));
+        }
+        Ok(Self::from_serialized_redeem_script(&redeem_script, chain))
+    }
+
+    /// Segwit address of a witness program, encoded with Bech32 for version 0 and Bech32m for
+    /// versions 1 to 16.
+    pub fn from_witness_program(
+        version: u8,
+        program: &[u8],
+        chain: Chain,
+    ) -> Result<Self, AddressError> {
+        Self::validate_witness_program(version, program)?;
+        Ok(Self {
+            chain,
+            payload: Payload::WitnessProgram {
+                version,
+                program: program.to_vec(),
+            },
+        })
+    }
+
+    pub fn chain(&self) -> &Chain {
+        &self.chain
+    }
+
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    /// Returns the address if it is valid on `chain`, now tagged with it. Testnet, signet and
+    /// regtest share base58 prefixes, so any of them is accepted for a base58 address of
+    /// another. Testnet and signet share segwit prefixes, while regtest has its own.
+    pub fn require_chain(self, chain: Chain) -> Result<Self, AddressError> {
+        let (expected, actual) = (chain.parameters(), self.chain.parameters());
+        let is_valid = match self.payload {
+            Payload::PubkeyHash(_) => expected.pubkey_hash_prefix == actual.pubkey_hash_prefix,
+            Payload::ScriptHash(_) => expected.script_hash_prefix == actual.script_hash_prefix,
+            Payload::WitnessProgram { .. } => expected.hrp == actual.hrp,
         };
-        Self(Self::base58_encode_with_checksum(&hash))
+        if is_valid {
+            Ok(Self { chain, ..self })
+        } else {
+            Err(AddressError::WrongChain(self.chain))
+        }
     }
 
-    fn base58_encode_with_checksum(input: &[u8]) -> String {
-        let mut input_with_checksum = Vec::with_capacity(input.len() + 32);
-        input_with_checksum.extend_from_slice(input);
-        input_with_checksum.extend_from_slice(&hash256(input)[..4]);
-        Self::base58_encode(&input_with_checksum)
+    /// The output script that pays to this address.
+    pub fn script_pubkey(&self) -> Script {
+        match &self.payload {
+            Payload::PubkeyHash(hash) => Script::p2pkh(hash),
+            Payload::ScriptHash(hash) => Script::p2sh(hash),
+            Payload::WitnessProgram { version, program } => {
+                Script::witness_program(*version, program)
+            }
+        }
     }
 
-    fn base58_encode(input: &[u8]) -> String {
-        const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
-        let input_base = to_base::<58>(input);
-        let mut result: Vec<u8> = input_base.iter().map(|b| ALPHABET[*b as usize]).collect();
+    fn from_serialized_public_key_base58_check(data: &[u8], chain: Chain) -> Self {
+        Self {
+            chain,
+            payload: Payload::PubkeyHash(hash160(data)),
+        }
+    }
 
-        let number_leading_zero_bytes = input.iter().take_while(|&&byte| byte == 0).count();
-        result.resize(number_leading_zero_bytes + result.len(), 0x31);
+    fn from_serialized_redeem_script(redeem_script: &[u8], chain: Chain) -> Self {
+        Self {
+            chain,
+            payload: Payload::ScriptHash(hash160(redeem_script)),
+        }
+    }
 
-        result.reverse();
+    fn validate_witness_program(version: u8, program: &[u8]) -> Result<(), AddressError> {
+        if version > 16 {
+            return Err(AddressError::InvalidWitnessVersion(version));
+        }
+        if !(2..=40).contains(&program.len())
+            || (version == 0 && program.len() != 20 && program.len() != 32)
+        {
+            return Err(AddressError::InvalidWitnessProgramLength(program.len()));
+        }
+        Ok(())
+    }
 
-        String::from_utf8(result).unwrap()
+    fn decode_base58(input: &str) -> Result<Self, AddressError> {
+        let data = base58::decode_check(input)?;
+        if data.len() != 21 {
+            return Err(AddressError::InvalidBase58Length(data.len()));
+        }
+        let hash: [u8; 20] = data[1..].try_into().unwrap();
+        let (chain, payload) = Chain::ALL
+            .into_iter()
+            .find_map(|chain| {
+                let parameters = chain.parameters();
+                if parameters.pubkey_hash_prefix == data[0] {
+                    Some((chain, Payload::PubkeyHash(hash)))
+                } else if parameters.script_hash_prefix == data[0] {
+                    Some((chain, Payload::ScriptHash(hash)))
+                } else {
+                    None
+                }
+            })
+            .ok_or(AddressError::UnknownVersionByte(data[0]))?;
+        Ok(Self { chain, payload })
+    }
+
+    /// Decodes a segwit address following BIP 173 and BIP 350.
+    fn decode_bech32(input: &str) -> Result<Self, AddressError> {
+        if input.len() > 90 {
+            return Err(AddressError::InvalidBech32Length(input.len()));
+        }
+        if input.chars().any(|c| c.is_ascii_lowercase())
+            && input.chars().any(|c| c.is_ascii_uppercase())
+        {
+            return Err(AddressError::MixedCase);
+        }
+        let input = input.to_ascii_lowercase();
+        // Callers only get here if the string has a separator
+        let (hrp, data) = input.rsplit_once('1').unwrap();
+        let chain = Chain::from_hrp(hrp)
+            .ok_or_else(|| AddressError::UnknownHumanReadablePart(hrp.to_string()))?;
+
+        let data = data
+            .chars()
+            .enumerate()
+            .map(|(i, character)| {
+                BECH32_ALPHABET
+                    .iter()
+                    .position(|&c| c as char == character)
+                    .map(|digit| digit as u8)
+                    .ok_or(AddressError::InvalidCharacter {
+                        character,
+                        position: hrp.len() + 1 + i,
+                    })
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        if data.len() < 6 {
+            return Err(AddressError::InvalidBech32Length(input.len()));
+        }
+
+        let mut enc = Self::expand_human_readable_part(hrp.as_bytes());
+        enc.extend_from_slice(&data);
+        let is_bech32m = match Self::bech32_polymod(&enc) {
+            1 => false,
+            BECH32M_CONSTANT => true,
+            _ => return Err(AddressError::InvalidChecksum),
+        };
+
+        let (&version, program) = data[..data.len() - 6]
+            .split_first()
+            .ok_or(AddressError::MissingWitnessVersion)?;
+        if version > 16 {
+            return Err(AddressError::InvalidWitnessVersion(version));
+        }
+        let program = convert_bits::<5, 8>(program, false).ok_or(AddressError::InvalidPadding)?;
+        Self::validate_witness_program(version, &program)?;
+        if (version == 0) == is_bech32m {
+            return Err(AddressError::WrongChecksumVariant);
+        }
+
+        Ok(Self {
+            chain,
+            payload: Payload::WitnessProgram { version, program },
+        })
     }
 
     fn bech32_polymod(bytes: &[u8]) -> u32 {
@@ -114,26 +413,19 @@ impl Address {
         c
     }
 
-    fn expand_human_readable_part(bytes: [u8; 2]) -> Vec<u8> {
-        let mut result = vec![0u8; 5];
-        let c = bytes[0];
-        result[0] = c >> 5;
-        result[3] = c & 0x1f;
-
-        let c = bytes[1];
-        result[1] = c >> 5;
-        result[4] = c & 0x1f;
-
-        result[2] = 0;
+    fn expand_human_readable_part(bytes: &[u8]) -> Vec<u8> {
+        let mut result: Vec<u8> = bytes.iter().map(|c| c >> 5).collect();
+        result.push(0);
+        result.extend(bytes.iter().map(|c| c & 0x1f));
         result
     }
 
-    fn bech32_checksum(bytes: &[u8], chain: Chain) -> [u8; 6] {
-        let mut enc = Self::expand_human_readable_part(chain.hrp()).to_vec();
+    fn bech32_checksum(bytes: &[u8], chain: Chain, is_bech32m: bool) -> [u8; 6] {
+        let mut enc = Self::expand_human_readable_part(chain.parameters().hrp.as_bytes());
         enc.extend_from_slice(bytes);
         enc.extend_from_slice(&[0u8; 6]);
-        // let m = Self::bech32_polymod(&enc) ^ 0x2bc830a3; // Bech32m
-        let m = Self::bech32_polymod(&enc) ^ 1; // Bech32
+        let constant = if is_bech32m { BECH32M_CONSTANT } else { 1 };
+        let m = Self::bech32_polymod(&enc) ^ constant;
         let mut result = [0u8; 6];
         for (i, byte) in result.iter_mut().enumerate() {
             *byte = ((m >> (5 * (5 - i))) as u8) & 31;
@@ -141,20 +433,20 @@ impl Address {
         result
     }
 
-    fn encode_bech32(bytes: &[u8], chain: Chain) -> String {
-        const ALPHABET: &[u8] = "qpzry9x8gf2tvdw0s3jn54khce6mua7l".as_bytes();
-        let mut input_base_32 = to_base::<32>(bytes);
-        input_base_32.push(0);
-        input_base_32.reverse();
-        let checksum = Self::bech32_checksum(&input_base_32, chain.clone());
+    /// Encodes a witness program following BIP 173, with the Bech32m checksum of BIP 350 for
+    /// witness versions other than 0.
+    fn encode_bech32(version: u8, program: &[u8], chain: Chain) -> String {
+        let mut input_base_32 = vec![version];
+        input_base_32.extend(convert_bits::<8, 5>(program, true).unwrap());
+        let checksum = Self::bech32_checksum(&input_base_32, chain, version != 0);
 
-        let mut result = chain.hrp().to_vec();
+        let mut result = chain.parameters().hrp.as_bytes().to_vec();
         result.push(49); // Separator "1"
         for c in input_base_32.iter() {
-            result.push(ALPHABET[*c as usize])
+            result.push(BECH32_ALPHABET[*c as usize])
         }
         for c in checksum.iter() {
-            result.push(ALPHABET[*c as usize])
+            result.push(BECH32_ALPHABET[*c as usize])
         }
         String::from_utf8(result).unwrap()
     }
@@ -162,94 +454,127 @@ impl Address {
 
 impl Display for Address {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        let parameters = self.chain.parameters();
+        let encoded = match &self.payload {
+            Payload::PubkeyHash(hash) => {
+                base58::encode_check(&[&[parameters.pubkey_hash_prefix], hash.as_slice()].concat())
+            }
+            Payload::ScriptHash(hash) => {
+                base58::encode_check(&[&[parameters.script_hash_prefix], hash.as_slice()].concat())
+            }
+            Payload::WitnessProgram { version, program } => {
+                Self::encode_bech32(*version, program, self.chain)
+            }
+        };
+        write!(f, "{}", encoded)
     }
 }
 
-fn to_base<const N: u32>(bytes: &[u8]) -> Vec<u8> {
-    let mut number = bytes.to_vec();
-    let mut input_base = Vec::new();
-    while !number.is_empty() {
-        let mut quotient = Vec::new();
-        let mut remainder = 0;
-        for byte in number.iter() {
-            let acc = *byte as u32 + 256 * remainder;
-            let digit = acc / N;
-            remainder = acc % N;
-
-            if digit > 0 || !quotient.is_empty() {
-                quotient.push(digit as u8);
+impl FromStr for Address {
+    type Err = AddressError;
+
+    /// Strings with a known human readable part, or that look like bech32 in every other way,
+    /// are decoded as segwit addresses and everything else as base58.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('1') {
+            Some((hrp, _)) if Chain::from_hrp(&hrp.to_ascii_lowercase()).is_some() => {
+                Self::decode_bech32(s)
             }
+            Some((hrp, data)) if looks_like_bech32(s, hrp, data) => Self::decode_bech32(s),
+            _ => Self::decode_base58(s),
         }
-        input_base.push(remainder as u8);
-        number = quotient;
     }
-    input_base
+}
+
+impl From<Base58Error> for AddressError {
+    fn from(error: Base58Error) -> Self {
+        Self::Base58(error)
+    }
+}
+
+/// Whether `input`, split at its last separator into `hrp` and `data`, has a single case and
+/// only bech32 characters after the separator. Base58 strings almost never do, as their
+/// alphabet has both cases and characters outside bech32 like `b` and `i`.
+fn looks_like_bech32(input: &str, hrp: &str, data: &str) -> bool {
+    let is_single_case = input == input.to_ascii_lowercase() || input == input.to_ascii_uppercase();
+    !hrp.is_empty()
+        && is_single_case
+        && data
+            .to_ascii_lowercase()
+            .bytes()
+            .all(|c| BECH32_ALPHABET.contains(&c))
+}
+
+/// Regroups a sequence of `FROM` bit values into `TO` bit values, most significant bits first.
+/// Without padding, fails if more than `FROM - 1` bits are left over or they are not all zero.
+fn convert_bits<const FROM: u32, const TO: u32>(data: &[u8], pad: bool) -> Option<Vec<u8>> {
+    let mut accumulator = 0u32;
+    let mut bits = 0;
+    let mut result = Vec::new();
+    for value in data {
+        accumulator = (accumulator << FROM) | *value as u32;
+        bits += FROM;
+        while bits >= TO {
+            bits -= TO;
+            result.push(((accumulator >> bits) & ((1 << TO) - 1)) as u8);
+        }
+        accumulator &= (1 << bits) - 1;
+    }
+    if pad {
+        if bits > 0 {
+            result.push((accumulator << (TO - bits)) as u8);
+        }
+    } else if bits >= FROM || accumulator != 0 {
+        return None;
+    }
+    Some(result)
 }
 
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
+
     use lambdaworks_math::unsigned_integer::element::U256;
 
-    use crate::{address::Encoding, public_key::PublicKey};
+    use crate::{
+        address::{AddressError, Encoding, Payload},
+        base58::Base58Error,
+        public_key::PublicKey,
+        schnorr::XOnlyPublicKey,
+        serializer::{CanParse, CanSerialize, PublicKeyCompressedSerializer, ScriptSerializer},
+        transaction::{decode_hex, Script},
+    };
 
     use super::{Address, Chain};
 
+    fn serialized_script_pubkey(address: &str) -> Vec<u8> {
+        let address = Address::from_str(address).unwrap();
+        // Drop the length prefix
+        ScriptSerializer::serialize(&address.script_pubkey())[1..].to_vec()
+    }
+
     #[test]
     fn test_address_1() {
         let public_key = PublicKey::from_u256(U256::from_u64(5002u64));
-        let expected_address = Address("mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA".to_string());
+        let expected_address = "mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA";
         let address = Address::new(&public_key, Chain::TestNet, Encoding::UncompressedBase58);
-        assert_eq!(address, expected_address);
+        assert_eq!(address.to_string(), expected_address);
     }
 
     #[test]
     fn test_address_2() {
         let public_key = PublicKey::from_u256(U256::from_u64(33632321603200000u64));
-        let expected_address = Address("mopVkxp8UhXqRYbCYJsbeE1h1fiF64jcoH".to_string());
+        let expected_address = "mopVkxp8UhXqRYbCYJsbeE1h1fiF64jcoH";
         let address = Address::new(&public_key, Chain::TestNet, Encoding::CompressedBase58);
-        assert_eq!(address, expected_address);
+        assert_eq!(address.to_string(), expected_address);
     }
 
     #[test]
     fn test_address_3() {
         let public_key = PublicKey::from_u256(U256::from_u64(0x12345deadbeefu64));
-        let expected_address = Address("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1".to_string());
+        let expected_address = "1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1";
         let address = Address::new(&public_key, Chain::MainNet, Encoding::CompressedBase58);
-        assert_eq!(address, expected_address);
-    }
-
-    #[test]
-    fn test_base58_encoding_1() {
-        let bytes = [
-            124, 7, 111, 243, 22, 105, 42, 61, 126, 179, 195, 187, 15, 139, 20, 136, 207, 114, 225,
-            175, 205, 146, 158, 41, 48, 112, 50, 153, 122, 131, 138, 61,
-        ];
-        let expected_string = "9MA8fRQrT4u8Zj8ZRd6MAiiyaxb2Y1CMpvVkHQu5hVM6".to_string();
-        let base58_encoded = Address::base58_encode(&bytes);
-        assert_eq!(base58_encoded, expected_string);
-    }
-
-    #[test]
-    fn test_base58_encoding_2() {
-        let bytes = [
-            239, 246, 158, 242, 177, 189, 147, 166, 110, 213, 33, 154, 221, 79, 181, 30, 17, 168,
-            64, 244, 4, 135, 99, 37, 161, 232, 255, 224, 82, 154, 44,
-        ];
-        let expected_string = "4fE3H2E6XMp4SsxtwinF7w9a34ooUrwWe4WsW1458Pd".to_string();
-        let base58_encoded = Address::base58_encode(&bytes);
-        assert_eq!(base58_encoded, expected_string);
-    }
-
-    #[test]
-    fn test_base58_encoding_3() {
-        let bytes = [
-            199, 32, 127, 238, 25, 125, 39, 198, 24, 174, 166, 33, 64, 111, 107, 245, 239, 111,
-            202, 56, 104, 29, 130, 178, 240, 111, 221, 189, 206, 111, 234, 182,
-        ];
-        let expected_string = "EQJsjkd6JaGwxrjEhfeqPenqHwrBmPQZjJGNSCHBkcF7".to_string();
-        let base58_encoded = Address::base58_encode(&bytes);
-        assert_eq!(base58_encoded, expected_string);
+        assert_eq!(address.to_string(), expected_address);
     }
 
     /// https://en.bitcoin.it/wiki/Technical_background_of_version_1_Bitcoin_addresses
@@ -258,29 +583,29 @@ mod tests {
         let private_key_u256 = U256::from_hex_unchecked(
             "18e14a7b6a307f426a94f8114701e7c8e774e7f9a47e2c2035db29a206321725",
         );
-        let expected_address = Address("1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAs".to_string());
+        let expected_address = "1PMycacnJaSqwwJqjawXBErnLsZ7RkXUAs";
         let public_key = PublicKey::from_u256(private_key_u256);
         let address = Address::new(&public_key, Chain::MainNet, Encoding::CompressedBase58);
 
-        assert_eq!(address, expected_address);
+        assert_eq!(address.to_string(), expected_address);
     }
 
     #[test]
     fn test_hrp() {
-        let expected_for_testnet = b"tb";
-        let expected_for_mainnet = b"bc";
-        assert_eq!(&Chain::MainNet.hrp(), expected_for_mainnet);
-        assert_eq!(&Chain::TestNet.hrp(), expected_for_testnet);
+        assert_eq!(Chain::MainNet.parameters().hrp, "bc");
+        assert_eq!(Chain::TestNet.parameters().hrp, "tb");
+        assert_eq!(Chain::Signet.parameters().hrp, "tb");
+        assert_eq!(Chain::Regtest.parameters().hrp, "bcrt");
     }
 
     #[test]
     fn test_expand_hrp() {
         let expected_for_testnet = [3, 3, 0, 20, 2];
-        let result_testnet = Address::expand_human_readable_part(Chain::TestNet.hrp());
+        let result_testnet = Address::expand_human_readable_part(b"tb");
         assert_eq!(result_testnet, expected_for_testnet);
 
         let expected_for_mainnet = [3, 3, 0, 2, 3];
-        let result_mainnet = Address::expand_human_readable_part(Chain::MainNet.hrp());
+        let result_mainnet = Address::expand_human_readable_part(b"bc");
         assert_eq!(result_mainnet, expected_for_mainnet);
     }
 
@@ -371,7 +696,7 @@ mod tests {
         ];
         let chain = Chain::MainNet;
         let expected_checksum = [12, 7, 9, 17, 11, 21];
-        let checksum = Address::bech32_checksum(&bytes, chain);
+        let checksum = Address::bech32_checksum(&bytes, chain, false);
         assert_eq!(checksum, expected_checksum);
     }
 
@@ -383,7 +708,469 @@ mod tests {
         ];
         let chain = Chain::MainNet;
         let expected_string = "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4".to_string();
-        let string = Address::encode_bech32(&bytes, chain);
+        let string = Address::encode_bech32(0, &bytes, chain);
         assert_eq!(string, expected_string);
     }
+
+    #[test]
+    fn test_parse_base58_address() {
+        let address = Address::from_str("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1").unwrap();
+        assert_eq!(address.chain(), &Chain::MainNet);
+        assert_eq!(
+            address.payload(),
+            &Payload::PubkeyHash([
+                153, 164, 198, 23, 80, 120, 146, 83, 246, 159, 215, 80, 172, 13, 2, 18, 99, 55, 51,
+                5
+            ])
+        );
+        assert_eq!(address.to_string(), "1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1");
+
+        let public_key = PublicKey::from_u256(U256::from_u64(5002u64));
+        let expected_address =
+            Address::new(&public_key, Chain::TestNet, Encoding::UncompressedBase58);
+        let address = Address::from_str("mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA").unwrap();
+        assert_eq!(address, expected_address);
+
+        let address = Address::from_str("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc").unwrap();
+        assert_eq!(address.chain(), &Chain::TestNet);
+        assert_eq!(
+            address.payload(),
+            &Payload::ScriptHash(
+                decode_hex("4e9f39ca4688ff102128ea4ccda34105324305b0")
+                    .unwrap()
+                    .try_into()
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_base58_script_pubkey() {
+        assert_eq!(
+            serialized_script_pubkey("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1"),
+            decode_hex("76a91499a4c61750789253f69fd750ac0d02126337330588ac").unwrap()
+        );
+        assert_eq!(
+            serialized_script_pubkey("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy"),
+            decode_hex("a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_base58_address() {
+        assert_eq!(
+            Address::from_str("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF2"),
+            Err(AddressError::Base58(Base58Error::InvalidChecksum))
+        );
+        // WIF of a private key, which has a 32 byte payload
+        assert_eq!(
+            Address::from_str("KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn"),
+            Err(AddressError::InvalidBase58Length(34))
+        );
+        let data = [[0x30].as_slice(), &[0u8; 20]].concat();
+        assert_eq!(
+            Address::from_str(&crate::base58::encode_check(&data)),
+            Err(AddressError::UnknownVersionByte(0x30))
+        );
+    }
+
+    /// Valid addresses from BIP 173 and BIP 350 with their scriptPubKey.
+    #[test]
+    fn test_parse_bech32_address() {
+        let vectors = [
+            (
+                "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4",
+                "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            ),
+            (
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+                "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            ("BC1SW50QGDZ25J", "6002751e"),
+            (
+                "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+                "5210751e76e8199196d454941c45d1b3a323",
+            ),
+            (
+                "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+                "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            ),
+        ];
+        for (address, script_pubkey) in vectors {
+            assert_eq!(
+                serialized_script_pubkey(address),
+                decode_hex(script_pubkey).unwrap()
+            );
+        }
+
+        let address =
+            Address::from_str("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0")
+                .unwrap();
+        assert_eq!(address.chain(), &Chain::MainNet);
+        assert_eq!(
+            address.payload(),
+            &Payload::WitnessProgram {
+                version: 1,
+                program: decode_hex(
+                    "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"
+                )
+                .unwrap()
+            }
+        );
+
+        let public_key = PublicKey::from_u256(U256::from_u64(5002u64));
+        let address = Address::new(&public_key, Chain::TestNet, Encoding::Bech32);
+        assert_eq!(Address::from_str(&address.to_string()).unwrap(), address);
+    }
+
+    /// Valid addresses from BIP 173 and BIP 350 built from their witness program.
+    #[test]
+    fn test_encode_witness_programs() {
+        let vectors = [
+            (
+                0,
+                "751e76e8199196d454941c45d1b3a323f1433bd6",
+                Chain::MainNet,
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+            ),
+            (
+                0,
+                "1863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+                Chain::TestNet,
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+            ),
+            (
+                1,
+                "751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6",
+                Chain::MainNet,
+                "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y",
+            ),
+            (16, "751e", Chain::MainNet, "bc1sw50qgdz25j"),
+            (
+                2,
+                "751e76e8199196d454941c45d1b3a323",
+                Chain::MainNet,
+                "bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs",
+            ),
+            (
+                1,
+                "000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433",
+                Chain::TestNet,
+                "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c",
+            ),
+            (
+                1,
+                "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                Chain::MainNet,
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+            ),
+        ];
+        for (version, program, chain, expected_address) in vectors {
+            let address =
+                Address::from_witness_program(version, &decode_hex(program).unwrap(), chain)
+                    .unwrap();
+            assert_eq!(address.to_string(), expected_address);
+            assert_eq!(Address::from_str(expected_address).unwrap(), address);
+        }
+    }
+
+    #[test]
+    fn test_encode_invalid_witness_programs() {
+        assert_eq!(
+            Address::from_witness_program(17, &[0u8; 32], Chain::MainNet),
+            Err(AddressError::InvalidWitnessVersion(17))
+        );
+        assert_eq!(
+            Address::from_witness_program(0, &[0u8; 16], Chain::MainNet),
+            Err(AddressError::InvalidWitnessProgramLength(16))
+        );
+        assert_eq!(
+            Address::from_witness_program(1, &[0u8; 41], Chain::MainNet),
+            Err(AddressError::InvalidWitnessProgramLength(41))
+        );
+        assert_eq!(
+            Address::from_witness_program(1, &[0u8; 1], Chain::MainNet),
+            Err(AddressError::InvalidWitnessProgramLength(1))
+        );
+    }
+
+    /// Invalid addresses from BIP 350.
+    #[test]
+    fn test_parse_invalid_bech32_address() {
+        let vectors = [
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+                AddressError::WrongChecksumVariant,
+            ),
+            (
+                "tb1z0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqglt7rf",
+                AddressError::WrongChecksumVariant,
+            ),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+                AddressError::WrongChecksumVariant,
+            ),
+            (
+                "bc1p38j9r5y49hruaue7wxjce0updqjuyyx0kh56v8s25huc6995vvpql3jow4",
+                AddressError::InvalidCharacter {
+                    character: 'o',
+                    position: 59,
+                },
+            ),
+            (
+                "BC130XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ7ZWS8R",
+                AddressError::InvalidWitnessVersion(17),
+            ),
+            ("bc1pw5dgrnzv", AddressError::InvalidWitnessProgramLength(1)),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v8n0nx0muaewav253zgeav",
+                AddressError::InvalidWitnessProgramLength(41),
+            ),
+            (
+                "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+                AddressError::InvalidWitnessProgramLength(16),
+            ),
+            (
+                "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq47Zagq",
+                AddressError::MixedCase,
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7v07qwwzcrf",
+                AddressError::InvalidPadding,
+            ),
+            (
+                "tb1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vpggkg4j",
+                AddressError::InvalidPadding,
+            ),
+            ("bc1gmk9yu", AddressError::MissingWitnessVersion),
+            (
+                "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+                AddressError::InvalidChecksum,
+            ),
+        ];
+        for (address, error) in vectors {
+            assert_eq!(Address::from_str(address), Err(error), "{address}");
+        }
+        assert_eq!(
+            Address::from_str("tc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq5zuyut"),
+            Err(AddressError::UnknownHumanReadablePart("tc".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_address_of_other_network() {
+        assert_eq!(
+            Address::from_str("ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9"),
+            Err(AddressError::UnknownHumanReadablePart("ltc".to_string()))
+        );
+        assert_eq!(
+            Address::from_str("LTC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KGMN4N9"),
+            Err(AddressError::UnknownHumanReadablePart("ltc".to_string()))
+        );
+        // Base58 addresses with a '1' are not mistaken for bech32
+        assert!(Address::from_str("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1").is_ok());
+    }
+
+    #[test]
+    fn test_require_chain() {
+        let address = Address::from_str("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1").unwrap();
+        assert!(address.clone().require_chain(Chain::MainNet).is_ok());
+        assert_eq!(
+            address.require_chain(Chain::TestNet),
+            Err(AddressError::WrongChain(Chain::MainNet))
+        );
+
+        let address = Address::from_str("mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA").unwrap();
+        assert_eq!(address.chain(), &Chain::TestNet);
+        let address = address.require_chain(Chain::Regtest).unwrap();
+        assert_eq!(address.chain(), &Chain::Regtest);
+        assert_eq!(address.to_string(), "mmTPbXQFxboEtNRkwfh6K51jvdtHLxGeMA");
+        let address = address.require_chain(Chain::Signet).unwrap();
+        assert_eq!(address.chain(), &Chain::Signet);
+        assert_eq!(
+            address.require_chain(Chain::MainNet),
+            Err(AddressError::WrongChain(Chain::Signet))
+        );
+
+        let address =
+            Address::from_str("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7")
+                .unwrap();
+        assert!(address.clone().require_chain(Chain::Signet).is_ok());
+        assert_eq!(
+            address.require_chain(Chain::Regtest),
+            Err(AddressError::WrongChain(Chain::TestNet))
+        );
+    }
+
+    #[test]
+    fn test_extended_key_prefixes() {
+        let mainnet = Chain::MainNet.parameters();
+        assert_eq!(
+            mainnet.extended_public_key_prefix,
+            0x0488b21eu32.to_be_bytes()
+        );
+        assert_eq!(
+            mainnet.extended_private_key_prefix,
+            0x0488ade4u32.to_be_bytes()
+        );
+        for chain in [Chain::TestNet, Chain::Signet, Chain::Regtest] {
+            let parameters = chain.parameters();
+            assert_eq!(
+                parameters.extended_public_key_prefix,
+                0x043587cfu32.to_be_bytes()
+            );
+            assert_eq!(
+                parameters.extended_private_key_prefix,
+                0x04358394u32.to_be_bytes()
+            );
+        }
+    }
+
+    #[test]
+    fn test_signet_and_regtest_addresses() {
+        let program = decode_hex("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        let address = Address::from_witness_program(0, &program, Chain::Regtest).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080"
+        );
+        assert_eq!(
+            Address::from_str("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080").unwrap(),
+            address
+        );
+        let address = Address::from_witness_program(0, &program, Chain::Signet).unwrap();
+        assert_eq!(
+            address.to_string(),
+            "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"
+        );
+
+        let public_key = PublicKey::from_u256(U256::from_u64(33632321603200000u64));
+        for chain in [Chain::Signet, Chain::Regtest] {
+            let address = Address::new(&public_key, chain, Encoding::CompressedBase58);
+            assert_eq!(address.to_string(), "mopVkxp8UhXqRYbCYJsbeE1h1fiF64jcoH");
+        }
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0049.mediawiki#test-vectors
+    #[test]
+    fn test_p2sh_p2wpkh_address() {
+        let (public_key, _) = PublicKeyCompressedSerializer::parse(
+            &decode_hex("03a1af804ac108a8a51782198c2d034b28bf90c8803f5a53f76276fa69a4eae77f")
+                .unwrap(),
+        )
+        .unwrap();
+        let address = Address::new(&public_key, Chain::TestNet, Encoding::P2shP2wpkh);
+        assert_eq!(address.to_string(), "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2");
+        assert_eq!(
+            ScriptSerializer::serialize(&address.script_pubkey())[1..],
+            decode_hex("a914336caa13e08b96080a32b5d818d59b4ab3b3674287").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_p2sh_multisig_address() {
+        let public_keys: Vec<PublicKey> = (1..=3)
+            .map(|i| PublicKey::from_u256(U256::from_u64(i)))
+            .collect();
+        let redeem_script = Script::multisig(2, &public_keys).unwrap();
+        assert_eq!(
+            ScriptSerializer::serialize_commands(&redeem_script),
+            decode_hex(
+                "52210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798\
+                 2102c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5\
+                 2102f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f953ae"
+            )
+            .unwrap()
+        );
+
+        let address = Address::p2sh(&redeem_script, Chain::MainNet).unwrap();
+        assert_eq!(address.to_string(), "33hG2q39jRi2NqicRJB4ggY1J8EJm97Szz");
+        let address = Address::p2sh(&redeem_script, Chain::TestNet).unwrap();
+        assert_eq!(address.to_string(), "2MuFU6ZyBLtDNadMA6RnwJdXGWUSUaoKLeS");
+        assert_eq!(
+            Address::from_str("2MuFU6ZyBLtDNadMA6RnwJdXGWUSUaoKLeS").unwrap(),
+            address
+        );
+    }
+
+    #[test]
+    fn test_p2sh_redeem_script_too_long() {
+        let public_keys: Vec<PublicKey> = (1..=16)
+            .map(|i| PublicKey::from_u256(U256::from_u64(i)))
+            .collect();
+        let redeem_script = Script::multisig(1, &public_keys).unwrap();
+        assert_eq!(
+            Address::p2sh(&redeem_script, Chain::MainNet),
+            Err(AddressError::RedeemScriptTooLong(547))
+        );
+    }
+
+    /// https://github.com/bitcoin/bips/blob/master/bip-0086.mediawiki#test-vectors
+    #[test]
+    fn test_taproot_address() {
+        let vectors = [
+            (
+                "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+                "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+            ),
+            (
+                "83dfe85a3151d2517290da461fe2815591ef69f2b18a2ce63f01697a8b313145",
+                "bc1p4qhjn9zdvkux4e44uhx8tc55attvtyu358kutcqkudyccelu0was9fqzwh",
+            ),
+            (
+                "399f1b2f4393f29a18c937859c5dd8a77350103157eb880f02e8c08214277cef",
+                "bc1p3qkhfews2uk44qtvauqyr2ttdsw7svhkl9nkm9s9c3x4ax5h60wqwruhk7",
+            ),
+        ];
+        for (internal_key, expected_address) in vectors {
+            // Both parities of the internal key give the same address
+            for prefix in ["02", "03"] {
+                let (public_key, _) = PublicKeyCompressedSerializer::parse(
+                    &decode_hex(&(prefix.to_owned() + internal_key)).unwrap(),
+                )
+                .unwrap();
+                let address = Address::new(
+                    &public_key,
+                    Chain::MainNet,
+                    Encoding::Taproot { merkle_root: None },
+                );
+                assert_eq!(address.to_string(), expected_address);
+            }
+        }
+    }
+
+    /// From the wallet test vectors of BIP 341.
+    #[test]
+    fn test_taproot_address_with_script_tree() {
+        let internal_key = XOnlyPublicKey::from_bytes(
+            &decode_hex("187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+        let merkle_root: [u8; 32] =
+            decode_hex("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        let address = Address::p2tr(&internal_key, Some(&merkle_root), Chain::MainNet);
+        assert_eq!(
+            address.to_string(),
+            "bc1pz37fc4cn9ah8anwm4xqqhvxygjf9rjf2resrw8h8w4tmvcs0863sa2e586"
+        );
+        assert_eq!(
+            ScriptSerializer::serialize(&address.script_pubkey())[1..],
+            decode_hex("5120147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3")
+                .unwrap()
+        );
+    }
 }
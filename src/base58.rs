@@ -0,0 +1,216 @@
+use crate::hash::hash256;
+
+/// Errors found while decoding base58 and base58check strings.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Base58Error {
+    /// A character outside of the base58 alphabet, with its position in the string.
+    InvalidCharacter { character: char, position: usize },
+    /// The decoded data is shorter than the 4 byte checksum.
+    TooShort,
+    /// The last 4 bytes do not match the hash of the data.
+    InvalidChecksum,
+}
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Encodes `input` in base58. Every leading zero byte is written as a leading '1'.
+pub fn encode(input: &[u8]) -> String {
+    let number_leading_zero_bytes = input.iter().take_while(|&&byte| byte == 0).count();
+    let input_base = to_base::<58>(&input[number_leading_zero_bytes..]);
+    let mut result: Vec<u8> = input_base.iter().map(|b| ALPHABET[*b as usize]).collect();
+    result.resize(number_leading_zero_bytes + result.len(), 0x31);
+
+    result.reverse();
+
+    String::from_utf8(result).unwrap()
+}
+
+/// Encodes `input` followed by the first 4 bytes of its `hash256` in base58.
+pub fn encode_check(input: &[u8]) -> String {
+    let mut input_with_checksum = Vec::with_capacity(input.len() + 4);
+    input_with_checksum.extend_from_slice(input);
+    input_with_checksum.extend_from_slice(&hash256(input)[..4]);
+    encode(&input_with_checksum)
+}
+
+/// Decodes a base58 string. Every leading '1' becomes a leading zero byte.
+pub fn decode(input: &str) -> Result<Vec<u8>, Base58Error> {
+    let digits = input
+        .chars()
+        .enumerate()
+        .map(|(position, character)| {
+            ALPHABET
+                .iter()
+                .position(|&c| c as char == character)
+                .map(|digit| digit as u8)
+                .ok_or(Base58Error::InvalidCharacter {
+                    character,
+                    position,
+                })
+        })
+        .collect::<Result<Vec<u8>, _>>()?;
+
+    let number_leading_zeros = digits.iter().take_while(|&&digit| digit == 0).count();
+    let mut result = vec![0u8; number_leading_zeros];
+    result.extend(from_base::<58>(&digits[number_leading_zeros..]));
+    Ok(result)
+}
+
+/// Decodes a base58check string, verifying and removing its 4 byte checksum.
+pub fn decode_check(input: &str) -> Result<Vec<u8>, Base58Error> {
+    let mut data = decode(input)?;
+    if data.len() < 4 {
+        return Err(Base58Error::TooShort);
+    }
+    let checksum = data.split_off(data.len() - 4);
+    if hash256(&data)[..4] != checksum[..] {
+        return Err(Base58Error::InvalidChecksum);
+    }
+    Ok(data)
+}
+
+/// Digits of the big endian integer `bytes` in base `N`, least significant first.
+fn to_base<const N: u32>(bytes: &[u8]) -> Vec<u8> {
+    let mut number = bytes.to_vec();
+    let mut input_base = Vec::new();
+    while !number.is_empty() {
+        let mut quotient = Vec::new();
+        let mut remainder = 0;
+        for byte in number.iter() {
+            let acc = *byte as u32 + 256 * remainder;
+            let digit = acc / N;
+            remainder = acc % N;
+
+            if digit > 0 || !quotient.is_empty() {
+                quotient.push(digit as u8);
+            }
+        }
+        input_base.push(remainder as u8);
+        number = quotient;
+    }
+    input_base
+}
+
+/// Inverse of `to_base`: converts the digits in base `N`, most significant first, to big endian
+/// bytes without leading zeros.
+fn from_base<const N: u32>(digits: &[u8]) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::new();
+    for digit in digits {
+        let mut carry = *digit as u32;
+        for byte in bytes.iter_mut().rev() {
+            let acc = *byte as u32 * N + carry;
+            *byte = (acc % 256) as u8;
+            carry = acc / 256;
+        }
+        while carry > 0 {
+            bytes.insert(0, (carry % 256) as u8);
+            carry /= 256;
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        base58::{decode, decode_check, encode, encode_check, Base58Error},
+        transaction::decode_hex,
+    };
+
+    #[test]
+    fn test_base58_encoding_1() {
+        let bytes = [
+            124, 7, 111, 243, 22, 105, 42, 61, 126, 179, 195, 187, 15, 139, 20, 136, 207, 114, 225,
+            175, 205, 146, 158, 41, 48, 112, 50, 153, 122, 131, 138, 61,
+        ];
+        let expected_string = "9MA8fRQrT4u8Zj8ZRd6MAiiyaxb2Y1CMpvVkHQu5hVM6".to_string();
+        let base58_encoded = encode(&bytes);
+        assert_eq!(base58_encoded, expected_string);
+    }
+
+    #[test]
+    fn test_base58_encoding_2() {
+        let bytes = [
+            239, 246, 158, 242, 177, 189, 147, 166, 110, 213, 33, 154, 221, 79, 181, 30, 17, 168,
+            64, 244, 4, 135, 99, 37, 161, 232, 255, 224, 82, 154, 44,
+        ];
+        let expected_string = "4fE3H2E6XMp4SsxtwinF7w9a34ooUrwWe4WsW1458Pd".to_string();
+        let base58_encoded = encode(&bytes);
+        assert_eq!(base58_encoded, expected_string);
+    }
+
+    #[test]
+    fn test_base58_encoding_3() {
+        let bytes = [
+            199, 32, 127, 238, 25, 125, 39, 198, 24, 174, 166, 33, 64, 111, 107, 245, 239, 111,
+            202, 56, 104, 29, 130, 178, 240, 111, 221, 189, 206, 111, 234, 182,
+        ];
+        let expected_string = "EQJsjkd6JaGwxrjEhfeqPenqHwrBmPQZjJGNSCHBkcF7".to_string();
+        let base58_encoded = encode(&bytes);
+        assert_eq!(base58_encoded, expected_string);
+    }
+
+    /// Vectors from `base58_encode_decode.json` of Bitcoin Core.
+    #[test]
+    fn test_base58_core_vectors() {
+        let vectors = [
+            ("", ""),
+            ("61", "2g"),
+            ("626262", "a3gV"),
+            ("636363", "aPEr"),
+            (
+                "73696d706c792061206c6f6e6720737472696e67",
+                "2cFupjhnEsSn59qHXstmK2ffpLv2",
+            ),
+            (
+                "00eb15231dfceb60925886b67d065299925915aeb172c06647",
+                "1NS17iag9jJgTHD1VXjvLCEnZuQ3rJDE9L",
+            ),
+            ("516b6fcd0f", "ABnLTmg"),
+            ("bf4f89001e670274dd", "3SEo3LWLoPntC"),
+            ("572e4794", "3EFU7m"),
+            ("ecac89cad93923c02321", "EJDM8drfXA6uyA"),
+            ("10c8511e", "Rt5zm"),
+            ("00000000000000000000", "1111111111"),
+        ];
+        for (hex, expected_string) in vectors {
+            let bytes = decode_hex(hex).unwrap();
+            assert_eq!(encode(&bytes), expected_string);
+            assert_eq!(decode(expected_string).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_base58_decoding_errors() {
+        assert_eq!(
+            decode("4fE3H0E6"),
+            Err(Base58Error::InvalidCharacter {
+                character: '0',
+                position: 5
+            })
+        );
+        assert_eq!(
+            decode("3SEo3LWLoPntC "),
+            Err(Base58Error::InvalidCharacter {
+                character: ' ',
+                position: 13
+            })
+        );
+    }
+
+    #[test]
+    fn test_base58_check() {
+        let data = [0x00, 0x01, 0x02, 0xff];
+        let encoded = encode_check(&data);
+        assert_eq!(decode_check(&encoded).unwrap(), data);
+
+        let decoded = decode_check("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF1").unwrap();
+        assert_eq!(decoded.len(), 21);
+        assert_eq!(decoded[0], 0x00);
+        assert_eq!(
+            decode_check("1F1Pn2y6pDb68E5nYJJeba4TLg2U7B6KF2"),
+            Err(Base58Error::InvalidChecksum)
+        );
+        assert_eq!(decode_check("1F1"), Err(Base58Error::TooShort));
+    }
+}
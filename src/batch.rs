@@ -0,0 +1,209 @@
+use lambdaworks_math::{cyclic_group::IsGroup, unsigned_integer::element::U256};
+
+use crate::{
+    random::{IsRandomGenerator, RandomScalarGenerator},
+    schnorr::{SchnorrSignature, SchnorrSignatureAlgorithm, XOnlyPublicKey},
+    secp256k1::{
+        curve::{multi_scalar_multiplication, Point, Secp256k1},
+        fields::{BaseFelt, ScalarFelt},
+    },
+    serializer::{CanSerialize, FeltSerializer},
+    signature::{Message, Signature},
+    PublicKey,
+};
+
+/// Returned by the batch verifiers when some signature of the batch is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchVerificationError {
+    failed_indices: Vec<usize>,
+}
+
+impl BatchVerificationError {
+    /// Positions in the batch of the items that failed verification, in increasing order.
+    pub fn failed_indices(&self) -> &[usize] {
+        &self.failed_indices
+    }
+}
+
+fn verify_one_by_one<T>(
+    items: &[T],
+    verify: impl Fn(&T) -> bool,
+) -> Result<(), BatchVerificationError> {
+    let failed_indices: Vec<usize> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| !verify(item))
+        .map(|(i, _)| i)
+        .collect();
+    if failed_indices.is_empty() {
+        Ok(())
+    } else {
+        Err(BatchVerificationError { failed_indices })
+    }
+}
+
+/// Verifies many BIP 340 signatures at once. The items are combined with random coefficients
+/// `a_i` (with `a_0 = 1`) and the whole batch is accepted if
+/// `(sum a_i s_i) G - sum a_i R_i - sum (a_i e_i) P_i` is the point at infinity, which takes a
+/// single multi-scalar multiplication. If the check fails, every item is verified on its own to
+/// find the invalid ones.
+pub fn verify_schnorr_batch(
+    items: &[(&[u8], &SchnorrSignature, &XOnlyPublicKey)],
+) -> Result<(), BatchVerificationError> {
+    if items.is_empty() || verify_schnorr_combined(items) {
+        return Ok(());
+    }
+    verify_one_by_one(items, |(message, signature, public_key)| {
+        public_key.verify(message, signature)
+    })
+}
+
+fn verify_schnorr_combined(items: &[(&[u8], &SchnorrSignature, &XOnlyPublicKey)]) -> bool {
+    let mut generator = RandomScalarGenerator::new();
+    let mut s_sum = ScalarFelt::zero();
+    let mut scalars: Vec<U256> = Vec::with_capacity(2 * items.len());
+    let mut points: Vec<Point> = Vec::with_capacity(2 * items.len());
+
+    for (i, (message, signature, public_key)) in items.iter().enumerate() {
+        let a = if i == 0 {
+            ScalarFelt::one()
+        } else {
+            generator.random_scalar()
+        };
+        let Some(nonce_point) = Secp256k1::lift_x(&signature.r, false) else {
+            return false;
+        };
+        let r = FeltSerializer::serialize(&signature.r);
+        let e = SchnorrSignatureAlgorithm::challenge(&r, &public_key.to_bytes(), message);
+
+        s_sum += &a * &signature.s;
+        scalars.push((-&a).representative());
+        points.push(nonce_point);
+        scalars.push((-(a * e)).representative());
+        points.push(public_key.point.clone());
+    }
+    multi_scalar_multiplication(&scalars, &points)
+        .operate_with(&Secp256k1::multiply_generator(s_sum.representative()))
+        .is_neutral_element()
+}
+
+/// Verifies many ECDSA signatures at once. A signature only fixes the x-coordinate of its nonce
+/// point R, so R can't be summed up with random coefficients like in [`verify_schnorr_batch`]
+/// without trying both of its lifts. Instead, every `u_i G + v_i P_i`, with `u_i = z_i / s_i`
+/// and `v_i = r_i / s_i`, is computed in projective coordinates and the whole batch is brought
+/// to affine coordinates with a single field inversion before comparing each x-coordinate with
+/// `r_i`. If some comparison fails, every item is verified on its own to find the invalid ones.
+pub fn verify_ecdsa_batch(
+    items: &[(&Message, &Signature, &PublicKey)],
+) -> Result<(), BatchVerificationError> {
+    if items.is_empty() || verify_ecdsa_combined(items) {
+        return Ok(());
+    }
+    verify_one_by_one(items, |(message, signature, public_key)| {
+        public_key.verify(message, signature)
+    })
+}
+
+fn verify_ecdsa_combined(items: &[(&Message, &Signature, &PublicKey)]) -> bool {
+    let mut points: Vec<Point> = Vec::with_capacity(items.len());
+    for (message, signature, public_key) in items {
+        if signature.r == ScalarFelt::zero() || public_key.point.is_neutral_element() {
+            return false;
+        }
+        let Ok(s_inv) = signature.s.inv() else {
+            return false;
+        };
+        let z = ScalarFelt::new((*message).into());
+        let u = z * &s_inv;
+        let v = &signature.r * s_inv;
+        let point = Secp256k1::multiply_generator_and_add(&u, &v, &public_key.point);
+        if point.is_neutral_element() {
+            return false;
+        }
+        points.push(point);
+    }
+
+    // x = X / Z for every point, with one inversion for all the Z
+    let mut z_inverses: Vec<BaseFelt> = points.iter().map(|point| point.z().clone()).collect();
+    if BaseFelt::inplace_batch_inverse(&mut z_inverses).is_err() {
+        return false;
+    }
+    items.iter().zip(points.iter().zip(z_inverses)).all(
+        |((_, signature, _), (point, z_inverse))| {
+            let x = point.x() * z_inverse;
+            ScalarFelt::new(x.representative()) == signature.r
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        batch::{verify_ecdsa_batch, verify_schnorr_batch},
+        schnorr::{SchnorrSignature, XOnlyPublicKey},
+        signature::{Message, Signature},
+        PrivateKey, PublicKey,
+    };
+
+    fn schnorr_items(size: u8) -> Vec<(Vec<u8>, SchnorrSignature, XOnlyPublicKey)> {
+        (1..=size)
+            .map(|i| {
+                let private_key = PrivateKey::new([i; 32]).unwrap();
+                let message = vec![i; i as usize];
+                let signature = private_key.sign_schnorr(&message);
+                let public_key = PublicKey::from_private_key(private_key).x_only_public_key();
+                (message, signature, public_key)
+            })
+            .collect()
+    }
+
+    fn ecdsa_items(size: u8) -> Vec<(Message, Signature, PublicKey)> {
+        (1..=size)
+            .map(|i| {
+                let private_key = PrivateKey::new([i; 32]).unwrap();
+                let message = Message::new([i.wrapping_mul(3); 32]);
+                let signature = private_key.sign(&message);
+                (message, signature, PublicKey::from_private_key(private_key))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_verify_schnorr_batch() {
+        let items = schnorr_items(10);
+        let batch: Vec<_> = items.iter().map(|(m, s, p)| (&m[..], s, p)).collect();
+        assert!(verify_schnorr_batch(&batch).is_ok());
+        assert!(verify_schnorr_batch(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_verify_schnorr_batch_reports_failing_items() {
+        let mut items = schnorr_items(10);
+        items[3].0.push(0);
+        items[7].1 = items[6].1.clone();
+
+        let batch: Vec<_> = items.iter().map(|(m, s, p)| (&m[..], s, p)).collect();
+        let error = verify_schnorr_batch(&batch).unwrap_err();
+        assert_eq!(error.failed_indices(), &[3, 7]);
+    }
+
+    #[test]
+    fn test_verify_ecdsa_batch() {
+        let items = ecdsa_items(10);
+        let batch: Vec<_> = items.iter().map(|(m, s, p)| (m, s, p)).collect();
+        assert!(verify_ecdsa_batch(&batch).is_ok());
+        assert!(verify_ecdsa_batch(&[]).is_ok());
+    }
+
+    #[test]
+    fn test_verify_ecdsa_batch_reports_failing_items() {
+        let mut items = ecdsa_items(10);
+        items[2].2 = PublicKey::from_private_key(PrivateKey::new([42; 32]).unwrap());
+        items[5].0 = Message::new([0; 32]);
+        items[8].1 = items[7].1.clone();
+
+        let batch: Vec<_> = items.iter().map(|(m, s, p)| (m, s, p)).collect();
+        let error = verify_ecdsa_batch(&batch).unwrap_err();
+        assert_eq!(error.failed_indices(), &[2, 5, 8]);
+    }
+}
@@ -1,4 +1,6 @@
+use hmac::{Hmac, Mac};
 use ripemd::{Digest as RipemdDigest, Ripemd160};
+use sha1::Sha1;
 use sha2::{Digest as Sha2Digest, Sha256};
 
 pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
@@ -17,13 +19,36 @@ pub(crate) fn ripemd160(data: &[u8]) -> [u8; 20] {
     hasher.finalize().into()
 }
 
+pub(crate) fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
 pub(crate) fn hash160(data: &[u8]) -> [u8; 20] {
     ripemd160(&sha256(data))
 }
 
+/// Hash of `data` under the domain separation tag `tag`, as defined in BIP 340:
+/// `sha256(sha256(tag) || sha256(tag) || data)`.
+pub(crate) fn tagged_hash(tag: &str, data: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256(tag.as_bytes());
+    let mut hasher = Sha256::new();
+    hasher.update(tag_hash);
+    hasher.update(tag_hash);
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
 #[cfg(test)]
 pub mod tests {
-    use crate::hash::{hash256, sha256};
+    use crate::hash::{hash256, hmac_sha256, sha256, tagged_hash};
 
     use super::ripemd160;
 
@@ -62,4 +87,29 @@ pub mod tests {
         ];
         assert_eq!(z, z_expected);
     }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // Test case 2 from RFC 4231
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+
+        // mac_expected = 0x5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843
+        let mac_expected = [
+            91, 220, 193, 70, 191, 96, 117, 78, 106, 4, 36, 38, 8, 149, 117, 199, 90, 0, 63, 8,
+            157, 39, 57, 131, 157, 236, 88, 185, 100, 236, 56, 67,
+        ];
+        assert_eq!(mac, mac_expected);
+    }
+
+    #[test]
+    fn test_tagged_hash() {
+        let z = tagged_hash("BIP0340/challenge", &[]);
+
+        // z_expected = 0xc216d352f5818b7b4beacd4ae0a26fe888080823d2a598856661bcd54f1b3713
+        let z_expected = [
+            194, 22, 211, 82, 245, 129, 139, 123, 75, 234, 205, 74, 224, 162, 111, 232, 136, 8, 8,
+            35, 210, 165, 152, 133, 102, 97, 188, 213, 79, 27, 55, 19,
+        ];
+        assert_eq!(z, z_expected);
+    }
 }
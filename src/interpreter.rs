@@ -0,0 +1,1098 @@
+use crate::{
+    hash::{hash160, hash256, ripemd160, sha1, sha256},
+    opcode::Opcode::{self, *},
+    serializer::{
+        CanParse, PublicKeyCompressedSerializer, PublicKeyUncompressedSerializer, ScriptSerializer,
+    },
+    signature::{Message, Signature},
+    transaction::{Command, Script, ScriptError},
+    PublicKey,
+};
+
+const MAX_SCRIPT_SIZE: usize = 10000;
+const MAX_SCRIPT_ELEMENT_SIZE: usize = 520;
+const MAX_OPS_PER_SCRIPT: usize = 201;
+const MAX_STACK_SIZE: usize = 1000;
+const MAX_PUBKEYS_PER_MULTISIG: i64 = 20;
+
+/// Sequence numbers with this bit set have no relative lock time (BIP 68).
+const SEQUENCE_LOCKTIME_DISABLE_FLAG: i64 = 1 << 31;
+
+pub type Stack = Vec<Vec<u8>>;
+
+/// The transaction being validated, as seen by the signature and lock time opcodes.
+pub trait SignatureChecker {
+    /// Whether `signature` by `public_key` signs the digest of the spending transaction that
+    /// `sighash_type` and `script_code` select. `script_code` is the executed script from its
+    /// last `OP_CODESEPARATOR` on, with the signatures removed.
+    fn check_signature(
+        &self,
+        signature: &Signature,
+        sighash_type: u8,
+        public_key: &PublicKey,
+        script_code: &Script,
+    ) -> bool;
+
+    /// Whether the transaction lock time satisfies `lock_time` (BIP 65).
+    fn check_lock_time(&self, _lock_time: i64) -> bool {
+        false
+    }
+
+    /// Whether the input sequence number satisfies `sequence` (BIP 112).
+    fn check_sequence(&self, _sequence: i64) -> bool {
+        false
+    }
+}
+
+/// Checks every signature against a digest computed beforehand, ignoring the sighash type and
+/// the script code. Lock time opcodes always fail.
+pub struct MessageChecker {
+    message: Message,
+}
+
+impl MessageChecker {
+    pub fn new(message: Message) -> Self {
+        Self { message }
+    }
+}
+
+impl SignatureChecker for MessageChecker {
+    fn check_signature(
+        &self,
+        signature: &Signature,
+        _sighash_type: u8,
+        public_key: &PublicKey,
+        _script_code: &Script,
+    ) -> bool {
+        public_key.verify(&self.message, signature)
+    }
+}
+
+/// Runs `script_sig` and then `script_pubkey` on the resulting stack, and succeeds if it ends
+/// with a true value on top. If `script_pubkey` is a P2SH output, the redeem script on top of
+/// the stack left by `script_sig` is also run (BIP 16).
+pub fn verify_script(
+    script_sig: &Script,
+    script_pubkey: &Script,
+    checker: &impl SignatureChecker,
+) -> Result<(), ScriptError> {
+    let mut stack = Stack::new();
+    eval_script(&mut stack, script_sig, checker)?;
+    let mut p2sh_stack = stack.clone();
+    eval_script(&mut stack, script_pubkey, checker)?;
+    if !stack.last().is_some_and(|top| cast_to_bool(top)) {
+        return Err(ScriptError::EvalFalse);
+    }
+
+    if script_pubkey.is_p2sh() {
+        if !script_sig.is_push_only() {
+            return Err(ScriptError::SigPushOnly);
+        }
+        // The stack can't be empty, or the script_pubkey would have failed
+        let redeem_script = p2sh_stack.pop().unwrap();
+        let redeem_script =
+            ScriptSerializer::parse_commands(&redeem_script).map_err(|_| ScriptError::BadOpcode)?;
+        eval_script(&mut p2sh_stack, &redeem_script, checker)?;
+        if !p2sh_stack.last().is_some_and(|top| cast_to_bool(top)) {
+            return Err(ScriptError::EvalFalse);
+        }
+    }
+    Ok(())
+}
+
+/// Runs `script` on `stack`, enforcing the consensus limits of Bitcoin Core.
+pub fn eval_script(
+    stack: &mut Stack,
+    script: &Script,
+    checker: &impl SignatureChecker,
+) -> Result<(), ScriptError> {
+    if ScriptSerializer::serialize_commands(script).len() > MAX_SCRIPT_SIZE {
+        return Err(ScriptError::ScriptSize);
+    }
+    let mut interpreter = Interpreter {
+        stack,
+        alt_stack: Stack::new(),
+        conditions: Vec::new(),
+        op_count: 0,
+        code_separator: 0,
+        script,
+        checker,
+    };
+    for (index, command) in script.commands().iter().enumerate() {
+        interpreter.step(index, command)?;
+    }
+    if !interpreter.conditions.is_empty() {
+        return Err(ScriptError::UnbalancedConditional);
+    }
+    Ok(())
+}
+
+struct Interpreter<'a, C> {
+    stack: &'a mut Stack,
+    alt_stack: Stack,
+    /// Whether each of the enclosing `OP_IF` branches is being executed.
+    conditions: Vec<bool>,
+    op_count: usize,
+    /// Index of the command after the last executed `OP_CODESEPARATOR`.
+    code_separator: usize,
+    script: &'a Script,
+    checker: &'a C,
+}
+
+impl<C: SignatureChecker> Interpreter<'_, C> {
+    fn step(&mut self, index: usize, command: &Command) -> Result<(), ScriptError> {
+        let executing = self.conditions.iter().all(|&condition| condition);
+        match command {
+            Command::Element(bytes) | Command::Push(_, bytes) => {
+                if bytes.len() > MAX_SCRIPT_ELEMENT_SIZE {
+                    return Err(ScriptError::PushSize);
+                }
+                if executing {
+                    self.stack.push(bytes.clone());
+                }
+            }
+            &Command::Operation(opcode) => {
+                if opcode > OP_16 {
+                    self.count_operations(1)?;
+                }
+                if opcode.is_disabled() {
+                    return Err(ScriptError::DisabledOpcode);
+                }
+                if executing || (OP_IF..=OP_ENDIF).contains(&opcode) {
+                    self.execute(index, opcode, executing)?;
+                }
+            }
+        }
+        if self.stack.len() + self.alt_stack.len() > MAX_STACK_SIZE {
+            return Err(ScriptError::StackSize);
+        }
+        Ok(())
+    }
+
+    fn execute(
+        &mut self,
+        index: usize,
+        opcode: Opcode,
+        executing: bool,
+    ) -> Result<(), ScriptError> {
+        match opcode {
+            OP_1NEGATE => self.push_number(-1),
+            _ if (OP_1..=OP_16).contains(&opcode) => {
+                self.push_number(opcode.small_integer().unwrap() as i64)
+            }
+            OP_NOP | OP_NOP1 | OP_NOP4 | OP_NOP5 | OP_NOP6 | OP_NOP7 | OP_NOP8 | OP_NOP9
+            | OP_NOP10 => {}
+
+            OP_IF | OP_NOTIF => {
+                let mut condition = false;
+                if executing {
+                    let top = self.pop().map_err(|_| ScriptError::UnbalancedConditional)?;
+                    condition = cast_to_bool(&top) == (opcode == OP_IF);
+                }
+                self.conditions.push(condition);
+            }
+            OP_ELSE => {
+                let condition = self
+                    .conditions
+                    .last_mut()
+                    .ok_or(ScriptError::UnbalancedConditional)?;
+                *condition = !*condition;
+            }
+            OP_ENDIF => {
+                self.conditions
+                    .pop()
+                    .ok_or(ScriptError::UnbalancedConditional)?;
+            }
+            OP_VERIFY => {
+                if !cast_to_bool(&self.pop()?) {
+                    return Err(ScriptError::Verify);
+                }
+            }
+            OP_RETURN => return Err(ScriptError::OpReturn),
+
+            OP_TOALTSTACK => {
+                let top = self.pop()?;
+                self.alt_stack.push(top);
+            }
+            OP_FROMALTSTACK => {
+                let top = self
+                    .alt_stack
+                    .pop()
+                    .ok_or(ScriptError::InvalidAltstackOperation)?;
+                self.stack.push(top);
+            }
+            OP_2DROP => {
+                self.pop()?;
+                self.pop()?;
+            }
+            OP_2DUP => self.copy_from(2, 2)?,
+            OP_3DUP => self.copy_from(3, 3)?,
+            OP_2OVER => self.copy_from(4, 2)?,
+            OP_2ROT => {
+                self.move_to_top(6)?;
+                self.move_to_top(6)?;
+            }
+            OP_2SWAP => {
+                self.move_to_top(4)?;
+                self.move_to_top(4)?;
+            }
+            OP_IFDUP => {
+                let top = self.peek(1)?.clone();
+                if cast_to_bool(&top) {
+                    self.stack.push(top);
+                }
+            }
+            OP_DEPTH => self.push_number(self.stack.len() as i64),
+            OP_DROP => {
+                self.pop()?;
+            }
+            OP_DUP => self.copy_from(1, 1)?,
+            OP_NIP => {
+                self.move_to_top(2)?;
+                self.pop()?;
+            }
+            OP_OVER => self.copy_from(2, 1)?,
+            OP_PICK | OP_ROLL => {
+                let depth = self.pop_number(4)?;
+                if depth < 0 || depth as usize >= self.stack.len() {
+                    return Err(ScriptError::InvalidStackOperation);
+                }
+                if opcode == OP_PICK {
+                    self.copy_from(depth as usize + 1, 1)?;
+                } else {
+                    self.move_to_top(depth as usize + 1)?;
+                }
+            }
+            OP_ROT => self.move_to_top(3)?,
+            OP_SWAP => self.move_to_top(2)?,
+            OP_TUCK => {
+                self.copy_from(1, 1)?;
+                self.move_to_top(3)?;
+                self.move_to_top(3)?;
+            }
+            OP_SIZE => {
+                let size = self.peek(1)?.len();
+                self.push_number(size as i64);
+            }
+
+            OP_EQUAL | OP_EQUALVERIFY => {
+                let equal = self.pop()? == self.pop()?;
+                self.push_bool(equal);
+                if opcode == OP_EQUALVERIFY {
+                    self.verify(ScriptError::EqualVerify)?;
+                }
+            }
+
+            OP_1ADD | OP_1SUB | OP_NEGATE | OP_ABS | OP_NOT | OP_0NOTEQUAL => {
+                let a = self.pop_number(4)?;
+                let result = match opcode {
+                    OP_1ADD => a + 1,
+                    OP_1SUB => a - 1,
+                    OP_NEGATE => -a,
+                    OP_ABS => a.abs(),
+                    OP_NOT => (a == 0) as i64,
+                    _ => (a != 0) as i64,
+                };
+                self.push_number(result);
+            }
+            OP_ADD
+            | OP_SUB
+            | OP_BOOLAND
+            | OP_BOOLOR
+            | OP_NUMEQUAL
+            | OP_NUMEQUALVERIFY
+            | OP_NUMNOTEQUAL
+            | OP_LESSTHAN
+            | OP_GREATERTHAN
+            | OP_LESSTHANOREQUAL
+            | OP_GREATERTHANOREQUAL
+            | OP_MIN
+            | OP_MAX => {
+                let b = self.pop_number(4)?;
+                let a = self.pop_number(4)?;
+                let result = match opcode {
+                    OP_ADD => a + b,
+                    OP_SUB => a - b,
+                    OP_BOOLAND => (a != 0 && b != 0) as i64,
+                    OP_BOOLOR => (a != 0 || b != 0) as i64,
+                    OP_NUMEQUAL | OP_NUMEQUALVERIFY => (a == b) as i64,
+                    OP_NUMNOTEQUAL => (a != b) as i64,
+                    OP_LESSTHAN => (a < b) as i64,
+                    OP_GREATERTHAN => (a > b) as i64,
+                    OP_LESSTHANOREQUAL => (a <= b) as i64,
+                    OP_GREATERTHANOREQUAL => (a >= b) as i64,
+                    OP_MIN => a.min(b),
+                    _ => a.max(b),
+                };
+                self.push_number(result);
+                if opcode == OP_NUMEQUALVERIFY {
+                    self.verify(ScriptError::NumEqualVerify)?;
+                }
+            }
+            OP_WITHIN => {
+                let max = self.pop_number(4)?;
+                let min = self.pop_number(4)?;
+                let x = self.pop_number(4)?;
+                self.push_bool(min <= x && x < max);
+            }
+
+            OP_RIPEMD160 => self.hash_top(|data| ripemd160(data).to_vec())?,
+            OP_SHA1 => self.hash_top(|data| sha1(data).to_vec())?,
+            OP_SHA256 => self.hash_top(|data| sha256(data).to_vec())?,
+            OP_HASH160 => self.hash_top(|data| hash160(data).to_vec())?,
+            OP_HASH256 => self.hash_top(|data| hash256(data).to_vec())?,
+            OP_CODESEPARATOR => self.code_separator = index + 1,
+            OP_CHECKSIG | OP_CHECKSIGVERIFY => {
+                let public_key = self.pop()?;
+                let signature = self.pop()?;
+                let script_code = self.script_code(&[&signature]);
+                let valid = self.check_signature(&signature, &public_key, &script_code);
+                self.push_bool(valid);
+                if opcode == OP_CHECKSIGVERIFY {
+                    self.verify(ScriptError::CheckSigVerify)?;
+                }
+            }
+            OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                let valid = self.check_multisig()?;
+                self.push_bool(valid);
+                if opcode == OP_CHECKMULTISIGVERIFY {
+                    self.verify(ScriptError::CheckMultisigVerify)?;
+                }
+            }
+            OP_CHECKLOCKTIMEVERIFY => {
+                let lock_time = decode_number(self.peek(1)?, 5)?;
+                if lock_time < 0 {
+                    return Err(ScriptError::NegativeLocktime);
+                }
+                if !self.checker.check_lock_time(lock_time) {
+                    return Err(ScriptError::UnsatisfiedLocktime);
+                }
+            }
+            OP_CHECKSEQUENCEVERIFY => {
+                let sequence = decode_number(self.peek(1)?, 5)?;
+                if sequence < 0 {
+                    return Err(ScriptError::NegativeLocktime);
+                }
+                if sequence & SEQUENCE_LOCKTIME_DISABLE_FLAG == 0
+                    && !self.checker.check_sequence(sequence)
+                {
+                    return Err(ScriptError::UnsatisfiedLocktime);
+                }
+            }
+
+            // OP_RESERVED, OP_VER, OP_VERIF, OP_VERNOTIF, OP_RESERVED1, OP_RESERVED2 and
+            // unassigned opcodes
+            _ => return Err(ScriptError::BadOpcode),
+        }
+        Ok(())
+    }
+
+    fn count_operations(&mut self, count: usize) -> Result<(), ScriptError> {
+        self.op_count += count;
+        if self.op_count > MAX_OPS_PER_SCRIPT {
+            return Err(ScriptError::OpCount);
+        }
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<Vec<u8>, ScriptError> {
+        self.stack.pop().ok_or(ScriptError::InvalidStackOperation)
+    }
+
+    /// The element at `depth`, where 1 is the top of the stack.
+    fn peek(&self, depth: usize) -> Result<&Vec<u8>, ScriptError> {
+        self.stack
+            .len()
+            .checked_sub(depth)
+            .map(|index| &self.stack[index])
+            .ok_or(ScriptError::InvalidStackOperation)
+    }
+
+    fn pop_number(&mut self, max_length: usize) -> Result<i64, ScriptError> {
+        decode_number(&self.pop()?, max_length)
+    }
+
+    fn push_number(&mut self, value: i64) {
+        self.stack.push(encode_number(value));
+    }
+
+    fn push_bool(&mut self, value: bool) {
+        self.push_number(value as i64);
+    }
+
+    /// Pushes copies of `count` elements starting at `depth`, keeping their order.
+    fn copy_from(&mut self, depth: usize, count: usize) -> Result<(), ScriptError> {
+        for _ in 0..count {
+            let element = self.peek(depth)?.clone();
+            self.stack.push(element);
+        }
+        Ok(())
+    }
+
+    /// Moves the element at `depth` to the top of the stack.
+    fn move_to_top(&mut self, depth: usize) -> Result<(), ScriptError> {
+        let index = self
+            .stack
+            .len()
+            .checked_sub(depth)
+            .ok_or(ScriptError::InvalidStackOperation)?;
+        let element = self.stack.remove(index);
+        self.stack.push(element);
+        Ok(())
+    }
+
+    /// Pops the top of the stack and fails with `error` if it is false.
+    fn verify(&mut self, error: ScriptError) -> Result<(), ScriptError> {
+        if cast_to_bool(&self.pop()?) {
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    fn hash_top(&mut self, hash: impl Fn(&[u8]) -> Vec<u8>) -> Result<(), ScriptError> {
+        let top = self.pop()?;
+        self.stack.push(hash(&top));
+        Ok(())
+    }
+
+    /// The script from the last `OP_CODESEPARATOR` on, without pushes of `signatures`. Like
+    /// `FindAndDelete` in Bitcoin Core, only the shortest pushes are removed.
+    fn script_code(&self, signatures: &[&Vec<u8>]) -> Script {
+        let commands = self.script.commands()[self.code_separator..]
+            .iter()
+            .filter(|command| {
+                !matches!(command, Command::Element(bytes) if signatures.contains(&bytes))
+            })
+            .cloned()
+            .collect();
+        // The commands come from a valid script
+        Script::new(commands).unwrap()
+    }
+
+    /// Signatures and public keys that can't be parsed make the check fail without an error.
+    fn check_signature(&self, signature: &[u8], public_key: &[u8], script_code: &Script) -> bool {
+        let Some((&sighash_type, der)) = signature.split_last() else {
+            return false;
+        };
+        let signature = match Signature::from_der(der) {
+            Ok((signature, length)) if length == der.len() => signature,
+            _ => return false,
+        };
+        let public_key = match public_key.first() {
+            Some(2 | 3) if public_key.len() == 33 => {
+                PublicKeyCompressedSerializer::parse(public_key)
+            }
+            Some(4) if public_key.len() == 65 => PublicKeyUncompressedSerializer::parse(public_key),
+            _ => return false,
+        };
+        let Ok((public_key, _)) = public_key else {
+            return false;
+        };
+        self.checker
+            .check_signature(&signature, sighash_type, &public_key, script_code)
+    }
+
+    fn check_multisig(&mut self) -> Result<bool, ScriptError> {
+        let key_count = self.pop_number(4)?;
+        if !(0..=MAX_PUBKEYS_PER_MULTISIG).contains(&key_count) {
+            return Err(ScriptError::PubkeyCount);
+        }
+        self.count_operations(key_count as usize)?;
+        let public_keys = (0..key_count)
+            .map(|_| self.pop())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let signature_count = self.pop_number(4)?;
+        if !(0..=key_count).contains(&signature_count) {
+            return Err(ScriptError::SigCount);
+        }
+        let signatures = (0..signature_count)
+            .map(|_| self.pop())
+            .collect::<Result<Vec<_>, _>>()?;
+        // An extra element is consumed because of an off by one error in the original client
+        self.pop()?;
+
+        let script_code = self.script_code(&signatures.iter().collect::<Vec<_>>());
+        // Signatures must appear in the same order as their public keys. Both were popped in
+        // reverse, so they are matched from the last ones.
+        let mut keys = public_keys.iter();
+        for signature in signatures.iter() {
+            if !keys
+                .by_ref()
+                .any(|public_key| self.check_signature(signature, public_key, &script_code))
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Decodes a little endian, sign and magnitude number of at most `max_length` bytes.
+pub(crate) fn decode_number(bytes: &[u8], max_length: usize) -> Result<i64, ScriptError> {
+    if bytes.len() > max_length {
+        return Err(ScriptError::NumberOverflow);
+    }
+    let Some((&last, _)) = bytes.split_last() else {
+        return Ok(0);
+    };
+    let mut magnitude = bytes
+        .iter()
+        .rev()
+        .fold(0i64, |acc, &byte| (acc << 8) | byte as i64);
+    let sign_bit = 0x80i64 << (8 * (bytes.len() - 1));
+    if last & 0x80 != 0 {
+        magnitude &= !sign_bit;
+        Ok(-magnitude)
+    } else {
+        Ok(magnitude)
+    }
+}
+
+/// Encodes a number in the shortest little endian, sign and magnitude form. Zero is empty.
+pub(crate) fn encode_number(value: i64) -> Vec<u8> {
+    let mut magnitude = value.unsigned_abs();
+    let mut result = Vec::new();
+    while magnitude > 0 {
+        result.push((magnitude & 0xff) as u8);
+        magnitude >>= 8;
+    }
+    let negative = if value < 0 { 0x80 } else { 0 };
+    match result.last_mut() {
+        Some(last) if *last & 0x80 != 0 => result.push(negative),
+        Some(last) => *last |= negative,
+        None => {}
+    }
+    result
+}
+
+/// Any encoding of zero, including negative zero, is false.
+fn cast_to_bool(bytes: &[u8]) -> bool {
+    match bytes.split_last() {
+        Some((&last, rest)) => rest.iter().any(|&byte| byte != 0) || (last & 0x7f) != 0,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        interpreter::{
+            cast_to_bool, decode_number, encode_number, eval_script, verify_script, MessageChecker,
+            SignatureChecker, Stack,
+        },
+        opcode::Opcode::*,
+        serializer::{CanSerialize, PublicKeyCompressedSerializer, ScriptSerializer},
+        signature::{Message, Signature},
+        transaction::{decode_hex, Command, Script, ScriptError},
+        Address, Chain, Encoding, PrivateKey, PublicKey,
+    };
+
+    fn script(commands: Vec<Command>) -> Script {
+        Script::new(commands).unwrap()
+    }
+
+    fn run(commands: Vec<Command>) -> Result<Stack, ScriptError> {
+        let mut stack = Stack::new();
+        let checker = MessageChecker::new(Message::new([0; 32]));
+        eval_script(&mut stack, &script(commands), &checker)?;
+        Ok(stack)
+    }
+
+    fn signature_bytes(private_key: &PrivateKey, message: &Message) -> Vec<u8> {
+        let mut signature = private_key.sign(message).to_der();
+        signature.push(0x01);
+        signature
+    }
+
+    use Command::{Element, Operation};
+
+    #[test]
+    fn test_number_encoding() {
+        let vectors: [(i64, &[u8]); 9] = [
+            (0, &[]),
+            (1, &[1]),
+            (-1, &[0x81]),
+            (127, &[0x7f]),
+            (128, &[0x80, 0]),
+            (-128, &[0x80, 0x80]),
+            (255, &[0xff, 0]),
+            (256, &[0, 1]),
+            (-255, &[0xff, 0x80]),
+        ];
+        for (number, bytes) in vectors {
+            assert_eq!(encode_number(number), bytes);
+            assert_eq!(decode_number(bytes, 4), Ok(number));
+        }
+        // Negative zero
+        assert_eq!(decode_number(&[0x80], 4), Ok(0));
+        assert_eq!(
+            decode_number(&[1, 2, 3, 4, 5], 4),
+            Err(ScriptError::NumberOverflow)
+        );
+        assert_eq!(decode_number(&[0, 0, 0, 0x80, 0], 5), Ok(1 << 31));
+    }
+
+    #[test]
+    fn test_cast_to_bool() {
+        assert!(!cast_to_bool(&[]));
+        assert!(!cast_to_bool(&[0]));
+        assert!(!cast_to_bool(&[0x80]));
+        assert!(!cast_to_bool(&[0, 0, 0x80]));
+        assert!(cast_to_bool(&[1]));
+        assert!(cast_to_bool(&[0x80, 0]));
+        assert!(cast_to_bool(&[0, 0x81]));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let script_pubkey = script(vec![
+            Operation(OP_5),
+            Operation(OP_ADD),
+            Operation(OP_9),
+            Operation(OP_EQUAL),
+        ]);
+        let checker = MessageChecker::new(Message::new([0; 32]));
+        assert!(verify_script(&script(vec![Operation(OP_4)]), &script_pubkey, &checker).is_ok());
+        assert_eq!(
+            verify_script(&script(vec![Operation(OP_3)]), &script_pubkey, &checker),
+            Err(ScriptError::EvalFalse)
+        );
+
+        // 3 - 5 = -2, -2 < 0 and 0 <= 2 < 3
+        assert_eq!(
+            run(vec![Operation(OP_3), Operation(OP_5), Operation(OP_SUB)]),
+            Ok(vec![vec![0x82]])
+        );
+        assert_eq!(
+            run(vec![
+                Element(vec![0x82]),
+                Element(vec![]),
+                Operation(OP_LESSTHAN),
+                Operation(OP_2),
+                Element(vec![]),
+                Operation(OP_3),
+                Operation(OP_WITHIN),
+            ]),
+            Ok(vec![vec![1], vec![1]])
+        );
+        assert_eq!(
+            run(vec![Element(vec![1, 2, 3, 4, 5]), Operation(OP_1ADD)]),
+            Err(ScriptError::NumberOverflow)
+        );
+    }
+
+    #[test]
+    fn test_conditionals() {
+        assert_eq!(
+            run(vec![
+                Operation(OP_1),
+                Operation(OP_IF),
+                Operation(OP_2),
+                Operation(OP_ELSE),
+                Operation(OP_3),
+                Operation(OP_ENDIF),
+            ]),
+            Ok(vec![vec![2]])
+        );
+        // The inner branch is skipped along with its OP_RETURN
+        assert_eq!(
+            run(vec![
+                Element(vec![]),
+                Operation(OP_IF),
+                Element(vec![]),
+                Operation(OP_NOTIF),
+                Operation(OP_RETURN),
+                Operation(OP_ENDIF),
+                Operation(OP_ELSE),
+                Operation(OP_7),
+                Operation(OP_ENDIF),
+            ]),
+            Ok(vec![vec![7]])
+        );
+        assert_eq!(
+            run(vec![Operation(OP_1), Operation(OP_IF)]),
+            Err(ScriptError::UnbalancedConditional)
+        );
+        assert_eq!(
+            run(vec![Operation(OP_ELSE)]),
+            Err(ScriptError::UnbalancedConditional)
+        );
+        assert_eq!(
+            run(vec![Operation(OP_IF)]),
+            Err(ScriptError::UnbalancedConditional)
+        );
+    }
+
+    #[test]
+    fn test_invalid_opcodes_in_skipped_branches() {
+        // OP_RESERVED only fails when executed
+        assert_eq!(
+            run(vec![
+                Element(vec![]),
+                Operation(OP_IF),
+                Operation(OP_RESERVED),
+                Operation(OP_ENDIF),
+            ]),
+            Ok(vec![])
+        );
+        assert_eq!(
+            run(vec![Operation(OP_RESERVED)]),
+            Err(ScriptError::BadOpcode)
+        );
+        // Disabled opcodes and OP_VERIF fail anyway
+        assert_eq!(
+            run(vec![
+                Element(vec![]),
+                Operation(OP_IF),
+                Operation(OP_CAT),
+                Operation(OP_ENDIF),
+            ]),
+            Err(ScriptError::DisabledOpcode)
+        );
+        assert_eq!(
+            run(vec![
+                Element(vec![]),
+                Operation(OP_IF),
+                Operation(OP_VERIF),
+                Operation(OP_ENDIF),
+            ]),
+            Err(ScriptError::BadOpcode)
+        );
+        assert_eq!(
+            run(vec![Operation(OP_CHECKSIGADD)]),
+            Err(ScriptError::BadOpcode)
+        );
+        assert_eq!(
+            run(vec![Operation(OP_1), Operation(OP_RETURN)]),
+            Err(ScriptError::OpReturn)
+        );
+    }
+
+    #[test]
+    fn test_stack_operations() {
+        let push = |values: &[u8]| -> Vec<Command> {
+            values.iter().map(|&value| Element(vec![value])).collect()
+        };
+        let run_on = |values: &[u8], operations: &[Command]| {
+            let mut commands = push(values);
+            commands.extend_from_slice(operations);
+            run(commands)
+        };
+        let stack = |values: &[u8]| -> Stack { values.iter().map(|&value| vec![value]).collect() };
+
+        assert_eq!(
+            run_on(&[1, 2, 3], &[Operation(OP_ROT)]),
+            Ok(stack(&[2, 3, 1]))
+        );
+        assert_eq!(run_on(&[1, 2], &[Operation(OP_SWAP)]), Ok(stack(&[2, 1])));
+        assert_eq!(
+            run_on(&[1, 2], &[Operation(OP_TUCK)]),
+            Ok(stack(&[2, 1, 2]))
+        );
+        assert_eq!(run_on(&[1, 2], &[Operation(OP_NIP)]), Ok(stack(&[2])));
+        assert_eq!(
+            run_on(&[1, 2], &[Operation(OP_OVER)]),
+            Ok(stack(&[1, 2, 1]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3, 4], &[Operation(OP_2SWAP)]),
+            Ok(stack(&[3, 4, 1, 2]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3, 4], &[Operation(OP_2OVER)]),
+            Ok(stack(&[1, 2, 3, 4, 1, 2]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3, 4, 5, 6], &[Operation(OP_2ROT)]),
+            Ok(stack(&[3, 4, 5, 6, 1, 2]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3], &[Operation(OP_3DUP)]),
+            Ok(stack(&[1, 2, 3, 1, 2, 3]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3, 2], &[Operation(OP_PICK)]),
+            Ok(stack(&[1, 2, 3, 1]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3, 2], &[Operation(OP_ROLL)]),
+            Ok(stack(&[2, 3, 1]))
+        );
+        assert_eq!(
+            run_on(&[1, 2, 3], &[Operation(OP_DEPTH)]),
+            Ok(stack(&[1, 2, 3, 3]))
+        );
+        assert_eq!(
+            run_on(
+                &[1, 2],
+                &[
+                    Operation(OP_TOALTSTACK),
+                    Operation(OP_DROP),
+                    Operation(OP_FROMALTSTACK)
+                ]
+            ),
+            Ok(stack(&[2]))
+        );
+
+        assert_eq!(
+            run_on(&[1, 2, 3], &[Operation(OP_PICK)]),
+            Err(ScriptError::InvalidStackOperation)
+        );
+        assert_eq!(
+            run(vec![Operation(OP_DROP)]),
+            Err(ScriptError::InvalidStackOperation)
+        );
+        assert_eq!(
+            run(vec![Operation(OP_FROMALTSTACK)]),
+            Err(ScriptError::InvalidAltstackOperation)
+        );
+    }
+
+    #[test]
+    fn test_hash_operations() {
+        assert_eq!(
+            run(vec![Element(vec![]), Operation(OP_SHA1)]),
+            Ok(vec![
+                decode_hex("da39a3ee5e6b4b0d3255bfef95601890afd80709").unwrap()
+            ])
+        );
+        assert_eq!(
+            run(vec![Element(vec![]), Operation(OP_SHA256)]),
+            Ok(vec![decode_hex(
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+            )
+            .unwrap()])
+        );
+    }
+
+    /// Pay to public key example of Programming Bitcoin.
+    #[test]
+    fn test_p2pk_spend() {
+        let message = Message::new(
+            decode_hex("7c076ff316692a3d7eb3c3bb0f8b1488cf72e1afcd929e29307032997a838a3d")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        );
+        let script_pubkey = script(vec![
+            Element(decode_hex(
+                "04887387e452b8eacc4acfde10d9aaf7f6d9a0f975aabb10d006e4da568744d06c61de6d95231cd89026e286df3b6ae4a894a3378e393e93a0f45b666329a0ae34",
+            ).unwrap()),
+            Operation(OP_CHECKSIG),
+        ]);
+        let script_sig = script(vec![Element(decode_hex(
+            "3045022000eff69ef2b1bd93a66ed5219add4fb51e11a840f404876325a1e8ffe0529a2c022100c7207fee197d27c618aea621406f6bf5ef6fca38681d82b2f06fddbdce6feab601",
+        ).unwrap())]);
+        assert!(verify_script(&script_sig, &script_pubkey, &MessageChecker::new(message)).is_ok());
+        assert_eq!(
+            verify_script(
+                &script_sig,
+                &script_pubkey,
+                &MessageChecker::new(Message::new([0; 32]))
+            ),
+            Err(ScriptError::EvalFalse)
+        );
+    }
+
+    #[test]
+    fn test_p2pkh_spend() {
+        let private_key = PrivateKey::new([7; 32]).unwrap();
+        let public_key = private_key.public_key();
+        let message = Message::new([1; 32]);
+        let script_pubkey =
+            Address::new(&public_key, Chain::MainNet, Encoding::CompressedBase58).script_pubkey();
+        let script_sig = script(vec![
+            Element(signature_bytes(&private_key, &message)),
+            Element(PublicKeyCompressedSerializer::serialize(&public_key).to_vec()),
+        ]);
+        let checker = MessageChecker::new(Message::new([1; 32]));
+        assert!(verify_script(&script_sig, &script_pubkey, &checker).is_ok());
+
+        let other_public_key = PrivateKey::new([8; 32]).unwrap().public_key();
+        let script_sig = script(vec![
+            Element(signature_bytes(&private_key, &message)),
+            Element(PublicKeyCompressedSerializer::serialize(&other_public_key).to_vec()),
+        ]);
+        assert_eq!(
+            verify_script(&script_sig, &script_pubkey, &checker),
+            Err(ScriptError::EqualVerify)
+        );
+    }
+
+    #[test]
+    fn test_p2sh_multisig_spend() {
+        let private_keys: Vec<PrivateKey> =
+            (1..=3).map(|i| PrivateKey::new([i; 32]).unwrap()).collect();
+        let public_keys: Vec<PublicKey> = private_keys.iter().map(|key| key.public_key()).collect();
+        let redeem_script = Script::multisig(2, &public_keys).unwrap();
+        let script_pubkey = Address::p2sh(&redeem_script, Chain::MainNet)
+            .unwrap()
+            .script_pubkey();
+        let message = Message::new([2; 32]);
+        let checker = MessageChecker::new(Message::new([2; 32]));
+        let script_sig = |signers: &[usize]| {
+            let mut commands = vec![Element(vec![])];
+            commands.extend(
+                signers
+                    .iter()
+                    .map(|&i| Element(signature_bytes(&private_keys[i], &message))),
+            );
+            commands.push(Element(ScriptSerializer::serialize_commands(
+                &redeem_script,
+            )));
+            script(commands)
+        };
+
+        assert!(verify_script(&script_sig(&[0, 2]), &script_pubkey, &checker).is_ok());
+        assert!(verify_script(&script_sig(&[1, 2]), &script_pubkey, &checker).is_ok());
+        // Signatures out of order
+        assert_eq!(
+            verify_script(&script_sig(&[2, 0]), &script_pubkey, &checker),
+            Err(ScriptError::EvalFalse)
+        );
+        assert_eq!(
+            verify_script(&script_sig(&[0]), &script_pubkey, &checker),
+            Err(ScriptError::InvalidStackOperation)
+        );
+
+        let mut commands = script_sig(&[0, 2]).commands().to_vec();
+        commands.insert(0, Operation(OP_NOP));
+        assert_eq!(
+            verify_script(&script(commands), &script_pubkey, &checker),
+            Err(ScriptError::SigPushOnly)
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        assert_eq!(run(vec![Operation(OP_NOP); 202]), Err(ScriptError::OpCount));
+        assert!(run(vec![Operation(OP_NOP); 201]).is_ok());
+        assert_eq!(run(vec![Element(vec![0; 521])]), Err(ScriptError::PushSize));
+        assert_eq!(
+            run(vec![Operation(OP_1); 1001]),
+            Err(ScriptError::StackSize)
+        );
+        assert_eq!(
+            run(vec![Element(vec![0; 520]); 20]),
+            Err(ScriptError::ScriptSize)
+        );
+        assert_eq!(
+            run(vec![Element(vec![21]), Operation(OP_CHECKMULTISIG)]),
+            Err(ScriptError::PubkeyCount)
+        );
+        assert_eq!(
+            run(vec![
+                Operation(OP_1),
+                Element(vec![]),
+                Operation(OP_CHECKMULTISIG)
+            ]),
+            Err(ScriptError::SigCount)
+        );
+    }
+
+    struct LockTimeChecker {
+        lock_time: i64,
+    }
+
+    impl SignatureChecker for LockTimeChecker {
+        fn check_signature(&self, _: &Signature, _: u8, _: &PublicKey, _: &Script) -> bool {
+            false
+        }
+
+        fn check_lock_time(&self, lock_time: i64) -> bool {
+            lock_time <= self.lock_time
+        }
+    }
+
+    #[test]
+    fn test_lock_time_operations() {
+        let cltv = |lock_time: Vec<u8>| {
+            script(vec![
+                Element(lock_time),
+                Operation(OP_CHECKLOCKTIMEVERIFY),
+                Operation(OP_DROP),
+                Operation(OP_1),
+            ])
+        };
+        let checker = LockTimeChecker { lock_time: 600 };
+        let empty = Script::empty();
+        assert!(verify_script(&empty, &cltv(encode_number(500)), &checker).is_ok());
+        assert_eq!(
+            verify_script(&empty, &cltv(encode_number(700)), &checker),
+            Err(ScriptError::UnsatisfiedLocktime)
+        );
+        assert_eq!(
+            verify_script(&empty, &cltv(encode_number(-1)), &checker),
+            Err(ScriptError::NegativeLocktime)
+        );
+
+        let csv = |sequence: i64| {
+            script(vec![
+                Element(encode_number(sequence)),
+                Operation(OP_CHECKSEQUENCEVERIFY),
+                Operation(OP_DROP),
+                Operation(OP_1),
+            ])
+        };
+        assert_eq!(
+            verify_script(&empty, &csv(10), &checker),
+            Err(ScriptError::UnsatisfiedLocktime)
+        );
+        // Disabled relative lock time
+        assert!(verify_script(&empty, &csv(1 << 31), &checker).is_ok());
+    }
+
+    /// Accepts a signature only if the script code it is checked against is `script_code`.
+    struct ScriptCodeChecker {
+        script_code: Script,
+    }
+
+    impl SignatureChecker for ScriptCodeChecker {
+        fn check_signature(
+            &self,
+            _: &Signature,
+            _: u8,
+            _: &PublicKey,
+            script_code: &Script,
+        ) -> bool {
+            *script_code == self.script_code
+        }
+    }
+
+    #[test]
+    fn test_script_code_removes_signatures() {
+        let private_key = PrivateKey::new([7; 32]).unwrap();
+        let signature = signature_bytes(&private_key, &Message::new([1; 32]));
+        let public_key =
+            PublicKeyCompressedSerializer::serialize(&private_key.public_key()).to_vec();
+        let script_sig = script(vec![Element(signature.clone())]);
+        let check_signature = |push: Command| {
+            vec![
+                push,
+                Operation(OP_DROP),
+                Element(public_key.clone()),
+                Operation(OP_CHECKSIG),
+            ]
+        };
+
+        let script_pubkey = script(check_signature(Element(signature.clone())));
+        let checker = ScriptCodeChecker {
+            script_code: script(vec![
+                Operation(OP_DROP),
+                Element(public_key.clone()),
+                Operation(OP_CHECKSIG),
+            ]),
+        };
+        assert!(verify_script(&script_sig, &script_pubkey, &checker).is_ok());
+
+        // Non-minimal pushes of the signature stay in the script code
+        let pushdata = Command::Push(OP_PUSHDATA1, signature.clone());
+        let script_pubkey = script(check_signature(pushdata.clone()));
+        let checker = ScriptCodeChecker {
+            script_code: script(check_signature(pushdata)),
+        };
+        assert!(verify_script(&script_sig, &script_pubkey, &checker).is_ok());
+    }
+}
@@ -1,14 +1,26 @@
 mod address;
+pub mod base58;
+mod batch;
 mod byte_array;
 mod hash;
+pub mod interpreter;
+mod opcode;
 mod private_key;
 mod public_key;
 mod random;
+mod schnorr;
 mod secp256k1;
 mod serializer;
 mod signature;
 mod transaction;
 
-pub use address::{Address, Chain, Encoding};
-pub use private_key::PrivateKey;
+pub use address::{Address, AddressError, Chain, Encoding, NetworkParameters, Payload};
+pub use base58::Base58Error;
+pub use batch::{verify_ecdsa_batch, verify_schnorr_batch, BatchVerificationError};
+pub use opcode::Opcode;
+pub use private_key::{PrivateKey, SecretKey, SecretKeyError, WifError};
 pub use public_key::PublicKey;
+pub use schnorr::{SchnorrSignature, XOnlyPublicKey};
+pub use serializer::{DERError, ParserError};
+pub use signature::{Message, RecoverableSignature, Signature};
+pub use transaction::{Command, Script, ScriptError};
@@ -0,0 +1,486 @@
+use std::fmt;
+
+macro_rules! opcodes {
+    ($($name:ident = $value:literal,)*) => {
+        /// Every byte a script can hold, named as in Bitcoin Core. Bytes `0x01` to `0x4b` push
+        /// that many bytes, and those with no meaning before Tapscript are `OP_SUCCESSx`.
+        #[allow(non_camel_case_types)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(u8)]
+        pub enum Opcode {
+            $($name = $value,)*
+        }
+
+        impl Opcode {
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Self::$name => stringify!($name),)*
+                }
+            }
+
+            /// The opcode with this name or one of the aliases `OP_FALSE`, `OP_TRUE`, `OP_NOP2`
+            /// and `OP_NOP3`.
+            pub fn from_name(name: &str) -> Option<Self> {
+                match name {
+                    $(stringify!($name) => Some(Self::$name),)*
+                    "OP_FALSE" => Some(Self::OP_FALSE),
+                    "OP_TRUE" => Some(Self::OP_TRUE),
+                    "OP_NOP2" => Some(Self::OP_NOP2),
+                    "OP_NOP3" => Some(Self::OP_NOP3),
+                    _ => None,
+                }
+            }
+        }
+
+        impl From<u8> for Opcode {
+            fn from(value: u8) -> Self {
+                match value {
+                    $($value => Self::$name,)*
+                }
+            }
+        }
+    };
+}
+
+opcodes! {
+    OP_0 = 0x00,
+    OP_PUSHBYTES_1 = 0x01,
+    OP_PUSHBYTES_2 = 0x02,
+    OP_PUSHBYTES_3 = 0x03,
+    OP_PUSHBYTES_4 = 0x04,
+    OP_PUSHBYTES_5 = 0x05,
+    OP_PUSHBYTES_6 = 0x06,
+    OP_PUSHBYTES_7 = 0x07,
+    OP_PUSHBYTES_8 = 0x08,
+    OP_PUSHBYTES_9 = 0x09,
+    OP_PUSHBYTES_10 = 0x0a,
+    OP_PUSHBYTES_11 = 0x0b,
+    OP_PUSHBYTES_12 = 0x0c,
+    OP_PUSHBYTES_13 = 0x0d,
+    OP_PUSHBYTES_14 = 0x0e,
+    OP_PUSHBYTES_15 = 0x0f,
+    OP_PUSHBYTES_16 = 0x10,
+    OP_PUSHBYTES_17 = 0x11,
+    OP_PUSHBYTES_18 = 0x12,
+    OP_PUSHBYTES_19 = 0x13,
+    OP_PUSHBYTES_20 = 0x14,
+    OP_PUSHBYTES_21 = 0x15,
+    OP_PUSHBYTES_22 = 0x16,
+    OP_PUSHBYTES_23 = 0x17,
+    OP_PUSHBYTES_24 = 0x18,
+    OP_PUSHBYTES_25 = 0x19,
+    OP_PUSHBYTES_26 = 0x1a,
+    OP_PUSHBYTES_27 = 0x1b,
+    OP_PUSHBYTES_28 = 0x1c,
+    OP_PUSHBYTES_29 = 0x1d,
+    OP_PUSHBYTES_30 = 0x1e,
+    OP_PUSHBYTES_31 = 0x1f,
+    OP_PUSHBYTES_32 = 0x20,
+    OP_PUSHBYTES_33 = 0x21,
+    OP_PUSHBYTES_34 = 0x22,
+    OP_PUSHBYTES_35 = 0x23,
+    OP_PUSHBYTES_36 = 0x24,
+    OP_PUSHBYTES_37 = 0x25,
+    OP_PUSHBYTES_38 = 0x26,
+    OP_PUSHBYTES_39 = 0x27,
+    OP_PUSHBYTES_40 = 0x28,
+    OP_PUSHBYTES_41 = 0x29,
+    OP_PUSHBYTES_42 = 0x2a,
+    OP_PUSHBYTES_43 = 0x2b,
+    OP_PUSHBYTES_44 = 0x2c,
+    OP_PUSHBYTES_45 = 0x2d,
+    OP_PUSHBYTES_46 = 0x2e,
+    OP_PUSHBYTES_47 = 0x2f,
+    OP_PUSHBYTES_48 = 0x30,
+    OP_PUSHBYTES_49 = 0x31,
+    OP_PUSHBYTES_50 = 0x32,
+    OP_PUSHBYTES_51 = 0x33,
+    OP_PUSHBYTES_52 = 0x34,
+    OP_PUSHBYTES_53 = 0x35,
+    OP_PUSHBYTES_54 = 0x36,
+    OP_PUSHBYTES_55 = 0x37,
+    OP_PUSHBYTES_56 = 0x38,
+    OP_PUSHBYTES_57 = 0x39,
+    OP_PUSHBYTES_58 = 0x3a,
+    OP_PUSHBYTES_59 = 0x3b,
+    OP_PUSHBYTES_60 = 0x3c,
+    OP_PUSHBYTES_61 = 0x3d,
+    OP_PUSHBYTES_62 = 0x3e,
+    OP_PUSHBYTES_63 = 0x3f,
+    OP_PUSHBYTES_64 = 0x40,
+    OP_PUSHBYTES_65 = 0x41,
+    OP_PUSHBYTES_66 = 0x42,
+    OP_PUSHBYTES_67 = 0x43,
+    OP_PUSHBYTES_68 = 0x44,
+    OP_PUSHBYTES_69 = 0x45,
+    OP_PUSHBYTES_70 = 0x46,
+    OP_PUSHBYTES_71 = 0x47,
+    OP_PUSHBYTES_72 = 0x48,
+    OP_PUSHBYTES_73 = 0x49,
+    OP_PUSHBYTES_74 = 0x4a,
+    OP_PUSHBYTES_75 = 0x4b,
+    OP_PUSHDATA1 = 0x4c,
+    OP_PUSHDATA2 = 0x4d,
+    OP_PUSHDATA4 = 0x4e,
+    OP_1NEGATE = 0x4f,
+    OP_RESERVED = 0x50,
+    OP_1 = 0x51,
+    OP_2 = 0x52,
+    OP_3 = 0x53,
+    OP_4 = 0x54,
+    OP_5 = 0x55,
+    OP_6 = 0x56,
+    OP_7 = 0x57,
+    OP_8 = 0x58,
+    OP_9 = 0x59,
+    OP_10 = 0x5a,
+    OP_11 = 0x5b,
+    OP_12 = 0x5c,
+    OP_13 = 0x5d,
+    OP_14 = 0x5e,
+    OP_15 = 0x5f,
+    OP_16 = 0x60,
+    OP_NOP = 0x61,
+    OP_VER = 0x62,
+    OP_IF = 0x63,
+    OP_NOTIF = 0x64,
+    OP_VERIF = 0x65,
+    OP_VERNOTIF = 0x66,
+    OP_ELSE = 0x67,
+    OP_ENDIF = 0x68,
+    OP_VERIFY = 0x69,
+    OP_RETURN = 0x6a,
+    OP_TOALTSTACK = 0x6b,
+    OP_FROMALTSTACK = 0x6c,
+    OP_2DROP = 0x6d,
+    OP_2DUP = 0x6e,
+    OP_3DUP = 0x6f,
+    OP_2OVER = 0x70,
+    OP_2ROT = 0x71,
+    OP_2SWAP = 0x72,
+    OP_IFDUP = 0x73,
+    OP_DEPTH = 0x74,
+    OP_DROP = 0x75,
+    OP_DUP = 0x76,
+    OP_NIP = 0x77,
+    OP_OVER = 0x78,
+    OP_PICK = 0x79,
+    OP_ROLL = 0x7a,
+    OP_ROT = 0x7b,
+    OP_SWAP = 0x7c,
+    OP_TUCK = 0x7d,
+    OP_CAT = 0x7e,
+    OP_SUBSTR = 0x7f,
+    OP_LEFT = 0x80,
+    OP_RIGHT = 0x81,
+    OP_SIZE = 0x82,
+    OP_INVERT = 0x83,
+    OP_AND = 0x84,
+    OP_OR = 0x85,
+    OP_XOR = 0x86,
+    OP_EQUAL = 0x87,
+    OP_EQUALVERIFY = 0x88,
+    OP_RESERVED1 = 0x89,
+    OP_RESERVED2 = 0x8a,
+    OP_1ADD = 0x8b,
+    OP_1SUB = 0x8c,
+    OP_2MUL = 0x8d,
+    OP_2DIV = 0x8e,
+    OP_NEGATE = 0x8f,
+    OP_ABS = 0x90,
+    OP_NOT = 0x91,
+    OP_0NOTEQUAL = 0x92,
+    OP_ADD = 0x93,
+    OP_SUB = 0x94,
+    OP_MUL = 0x95,
+    OP_DIV = 0x96,
+    OP_MOD = 0x97,
+    OP_LSHIFT = 0x98,
+    OP_RSHIFT = 0x99,
+    OP_BOOLAND = 0x9a,
+    OP_BOOLOR = 0x9b,
+    OP_NUMEQUAL = 0x9c,
+    OP_NUMEQUALVERIFY = 0x9d,
+    OP_NUMNOTEQUAL = 0x9e,
+    OP_LESSTHAN = 0x9f,
+    OP_GREATERTHAN = 0xa0,
+    OP_LESSTHANOREQUAL = 0xa1,
+    OP_GREATERTHANOREQUAL = 0xa2,
+    OP_MIN = 0xa3,
+    OP_MAX = 0xa4,
+    OP_WITHIN = 0xa5,
+    OP_RIPEMD160 = 0xa6,
+    OP_SHA1 = 0xa7,
+    OP_SHA256 = 0xa8,
+    OP_HASH160 = 0xa9,
+    OP_HASH256 = 0xaa,
+    OP_CODESEPARATOR = 0xab,
+    OP_CHECKSIG = 0xac,
+    OP_CHECKSIGVERIFY = 0xad,
+    OP_CHECKMULTISIG = 0xae,
+    OP_CHECKMULTISIGVERIFY = 0xaf,
+    OP_NOP1 = 0xb0,
+    OP_CHECKLOCKTIMEVERIFY = 0xb1,
+    OP_CHECKSEQUENCEVERIFY = 0xb2,
+    OP_NOP4 = 0xb3,
+    OP_NOP5 = 0xb4,
+    OP_NOP6 = 0xb5,
+    OP_NOP7 = 0xb6,
+    OP_NOP8 = 0xb7,
+    OP_NOP9 = 0xb8,
+    OP_NOP10 = 0xb9,
+    OP_CHECKSIGADD = 0xba,
+    OP_SUCCESS187 = 0xbb,
+    OP_SUCCESS188 = 0xbc,
+    OP_SUCCESS189 = 0xbd,
+    OP_SUCCESS190 = 0xbe,
+    OP_SUCCESS191 = 0xbf,
+    OP_SUCCESS192 = 0xc0,
+    OP_SUCCESS193 = 0xc1,
+    OP_SUCCESS194 = 0xc2,
+    OP_SUCCESS195 = 0xc3,
+    OP_SUCCESS196 = 0xc4,
+    OP_SUCCESS197 = 0xc5,
+    OP_SUCCESS198 = 0xc6,
+    OP_SUCCESS199 = 0xc7,
+    OP_SUCCESS200 = 0xc8,
+    OP_SUCCESS201 = 0xc9,
+    OP_SUCCESS202 = 0xca,
+    OP_SUCCESS203 = 0xcb,
+    OP_SUCCESS204 = 0xcc,
+    OP_SUCCESS205 = 0xcd,
+    OP_SUCCESS206 = 0xce,
+    OP_SUCCESS207 = 0xcf,
+    OP_SUCCESS208 = 0xd0,
+    OP_SUCCESS209 = 0xd1,
+    OP_SUCCESS210 = 0xd2,
+    OP_SUCCESS211 = 0xd3,
+    OP_SUCCESS212 = 0xd4,
+    OP_SUCCESS213 = 0xd5,
+    OP_SUCCESS214 = 0xd6,
+    OP_SUCCESS215 = 0xd7,
+    OP_SUCCESS216 = 0xd8,
+    OP_SUCCESS217 = 0xd9,
+    OP_SUCCESS218 = 0xda,
+    OP_SUCCESS219 = 0xdb,
+    OP_SUCCESS220 = 0xdc,
+    OP_SUCCESS221 = 0xdd,
+    OP_SUCCESS222 = 0xde,
+    OP_SUCCESS223 = 0xdf,
+    OP_SUCCESS224 = 0xe0,
+    OP_SUCCESS225 = 0xe1,
+    OP_SUCCESS226 = 0xe2,
+    OP_SUCCESS227 = 0xe3,
+    OP_SUCCESS228 = 0xe4,
+    OP_SUCCESS229 = 0xe5,
+    OP_SUCCESS230 = 0xe6,
+    OP_SUCCESS231 = 0xe7,
+    OP_SUCCESS232 = 0xe8,
+    OP_SUCCESS233 = 0xe9,
+    OP_SUCCESS234 = 0xea,
+    OP_SUCCESS235 = 0xeb,
+    OP_SUCCESS236 = 0xec,
+    OP_SUCCESS237 = 0xed,
+    OP_SUCCESS238 = 0xee,
+    OP_SUCCESS239 = 0xef,
+    OP_SUCCESS240 = 0xf0,
+    OP_SUCCESS241 = 0xf1,
+    OP_SUCCESS242 = 0xf2,
+    OP_SUCCESS243 = 0xf3,
+    OP_SUCCESS244 = 0xf4,
+    OP_SUCCESS245 = 0xf5,
+    OP_SUCCESS246 = 0xf6,
+    OP_SUCCESS247 = 0xf7,
+    OP_SUCCESS248 = 0xf8,
+    OP_SUCCESS249 = 0xf9,
+    OP_SUCCESS250 = 0xfa,
+    OP_SUCCESS251 = 0xfb,
+    OP_SUCCESS252 = 0xfc,
+    OP_SUCCESS253 = 0xfd,
+    OP_SUCCESS254 = 0xfe,
+    OP_INVALIDOPCODE = 0xff,
+}
+
+impl Opcode {
+    pub const OP_FALSE: Self = Self::OP_0;
+    pub const OP_TRUE: Self = Self::OP_1;
+    pub const OP_NOP2: Self = Self::OP_CHECKLOCKTIMEVERIFY;
+    pub const OP_NOP3: Self = Self::OP_CHECKSEQUENCEVERIFY;
+
+    /// `OP_1` to `OP_16` for `n` between 1 and 16.
+    pub fn from_small_integer(n: u8) -> Option<Self> {
+        match n {
+            1..=16 => Some(Self::from(Self::OP_1 as u8 + n - 1)),
+            _ => None,
+        }
+    }
+
+    /// The number pushed by `OP_1` to `OP_16`.
+    pub fn small_integer(self) -> Option<u8> {
+        (Self::OP_1..=Self::OP_16)
+            .contains(&self)
+            .then(|| self as u8 - Self::OP_1 as u8 + 1)
+    }
+
+    /// The opcode that pushes `data` in the fewest bytes (BIP 62): `OP_0`, `OP_1NEGATE` and
+    /// `OP_1` to `OP_16` for the values they push, then direct pushes up to 75 bytes and
+    /// `OP_PUSHDATA1`, `OP_PUSHDATA2` or `OP_PUSHDATA4` as needed.
+    pub fn minimal_push(data: &[u8]) -> Self {
+        match data {
+            [] => Self::OP_0,
+            [0x81] => Self::OP_1NEGATE,
+            &[n @ 1..=16] => Self::from(Self::OP_1 as u8 + n - 1),
+            _ => match data.len() {
+                length @ 1..=75 => Self::from(length as u8),
+                76..=0xff => Self::OP_PUSHDATA1,
+                0x100..=0xffff => Self::OP_PUSHDATA2,
+                _ => Self::OP_PUSHDATA4,
+            },
+        }
+    }
+
+    /// Whether it only pushes data or a number, which is everything up to `OP_16` (including
+    /// `OP_RESERVED`, as Bitcoin Core does).
+    pub fn is_push(self) -> bool {
+        self <= Self::OP_16
+    }
+
+    /// Opcodes that make a script fail even in a branch that is not executed.
+    pub fn is_disabled(self) -> bool {
+        matches!(
+            self,
+            Self::OP_CAT
+                | Self::OP_SUBSTR
+                | Self::OP_LEFT
+                | Self::OP_RIGHT
+                | Self::OP_INVERT
+                | Self::OP_AND
+                | Self::OP_OR
+                | Self::OP_XOR
+                | Self::OP_2MUL
+                | Self::OP_2DIV
+                | Self::OP_MUL
+                | Self::OP_DIV
+                | Self::OP_MOD
+                | Self::OP_LSHIFT
+                | Self::OP_RSHIFT
+        )
+    }
+
+    /// Opcodes that make a script fail when executed, but not in a branch that is skipped
+    /// (except `OP_VERIF` and `OP_VERNOTIF`, which always fail).
+    pub fn is_reserved(self) -> bool {
+        matches!(
+            self,
+            Self::OP_RESERVED
+                | Self::OP_VER
+                | Self::OP_VERIF
+                | Self::OP_VERNOTIF
+                | Self::OP_RESERVED1
+                | Self::OP_RESERVED2
+        )
+    }
+
+    /// Opcodes that make a Tapscript succeed unconditionally (BIP 342).
+    pub fn is_success(self) -> bool {
+        matches!(
+            self as u8,
+            80 | 98 | 126..=129 | 131..=134 | 137..=138 | 141..=142 | 149..=153 | 187..=254
+        )
+    }
+}
+
+impl From<Opcode> for u8 {
+    fn from(opcode: Opcode) -> Self {
+        opcode as u8
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Opcode;
+
+    #[test]
+    fn test_byte_round_trip() {
+        for byte in 0..=u8::MAX {
+            assert_eq!(u8::from(Opcode::from(byte)), byte);
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Opcode::from(0x76).name(), "OP_DUP");
+        assert_eq!(Opcode::from(0xa9).to_string(), "OP_HASH160");
+        assert_eq!(Opcode::from(0xae).name(), "OP_CHECKMULTISIG");
+        assert_eq!(Opcode::from(0xb1).name(), "OP_CHECKLOCKTIMEVERIFY");
+        assert_eq!(Opcode::from(0x14).name(), "OP_PUSHBYTES_20");
+        assert_eq!(Opcode::from(0xbb).name(), "OP_SUCCESS187");
+        assert_eq!(Opcode::from(0xff).name(), "OP_INVALIDOPCODE");
+        assert_eq!(Opcode::OP_NOP2, Opcode::OP_CHECKLOCKTIMEVERIFY);
+        assert_eq!(Opcode::OP_TRUE, Opcode::OP_1);
+
+        for byte in 0..=u8::MAX {
+            let opcode = Opcode::from(byte);
+            assert_eq!(Opcode::from_name(opcode.name()), Some(opcode));
+        }
+        assert_eq!(
+            Opcode::from_name("OP_NOP3"),
+            Some(Opcode::OP_CHECKSEQUENCEVERIFY)
+        );
+        assert_eq!(Opcode::from_name("OP_FALSE"), Some(Opcode::OP_0));
+        assert_eq!(Opcode::from_name("DUP"), None);
+    }
+
+    #[test]
+    fn test_small_integers() {
+        for n in 1..=16 {
+            let opcode = Opcode::from_small_integer(n).unwrap();
+            assert_eq!(u8::from(opcode), 0x50 + n);
+            assert_eq!(opcode.small_integer(), Some(n));
+        }
+        assert_eq!(Opcode::from_small_integer(0), None);
+        assert_eq!(Opcode::from_small_integer(17), None);
+        assert_eq!(Opcode::OP_0.small_integer(), None);
+        assert_eq!(Opcode::OP_NOP.small_integer(), None);
+    }
+
+    #[test]
+    fn test_minimal_push() {
+        assert_eq!(Opcode::minimal_push(&[]), Opcode::OP_0);
+        assert_eq!(Opcode::minimal_push(&[0]), Opcode::OP_PUSHBYTES_1);
+        assert_eq!(Opcode::minimal_push(&[1]), Opcode::OP_1);
+        assert_eq!(Opcode::minimal_push(&[16]), Opcode::OP_16);
+        assert_eq!(Opcode::minimal_push(&[17]), Opcode::OP_PUSHBYTES_1);
+        assert_eq!(Opcode::minimal_push(&[0x81]), Opcode::OP_1NEGATE);
+        assert_eq!(Opcode::minimal_push(&[1, 0]), Opcode::OP_PUSHBYTES_2);
+        assert_eq!(Opcode::minimal_push(&[0; 75]), Opcode::OP_PUSHBYTES_75);
+        assert_eq!(Opcode::minimal_push(&[0; 76]), Opcode::OP_PUSHDATA1);
+        assert_eq!(Opcode::minimal_push(&[0; 0xff]), Opcode::OP_PUSHDATA1);
+        assert_eq!(Opcode::minimal_push(&[0; 0x100]), Opcode::OP_PUSHDATA2);
+        assert_eq!(Opcode::minimal_push(&[0; 0xffff]), Opcode::OP_PUSHDATA2);
+        assert_eq!(Opcode::minimal_push(&[0; 0x10000]), Opcode::OP_PUSHDATA4);
+    }
+
+    #[test]
+    fn test_classification() {
+        let all = || (0..=u8::MAX).map(Opcode::from);
+        assert_eq!(all().filter(|opcode| opcode.is_push()).count(), 0x61);
+        assert!(Opcode::OP_PUSHDATA4.is_push());
+        assert!(!Opcode::OP_NOP.is_push());
+        assert_eq!(all().filter(|opcode| opcode.is_disabled()).count(), 15);
+        assert!(Opcode::OP_CAT.is_disabled());
+        assert!(!Opcode::OP_EQUAL.is_disabled());
+        assert_eq!(all().filter(|opcode| opcode.is_reserved()).count(), 6);
+        assert!(Opcode::OP_VERIF.is_reserved());
+        assert_eq!(all().filter(|opcode| opcode.is_success()).count(), 87);
+        assert!(Opcode::OP_CAT.is_success());
+        assert!(!Opcode::OP_CHECKSIGADD.is_success());
+        assert!(!Opcode::OP_INVALIDOPCODE.is_success());
+    }
+}
@@ -1,3 +1,450 @@
-use crate::byte_array::ByteArrayOfLength32;
+use std::fmt;
 
-pub type PrivateKey = ByteArrayOfLength32;
+use lambdaworks_math::{
+    field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
+};
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroize;
+
+use crate::{
+    address::Chain,
+    base58::{self, Base58Error},
+    byte_array::ByteArrayOfLength32,
+    random::DeterministicScalarGenerator,
+    schnorr::{SchnorrSignature, SchnorrSignatureAlgorithm},
+    secp256k1::{
+        constant_time,
+        fields::{ScalarFelt, ScalarFieldModulus},
+    },
+    signature::{
+        EllipticCurveDigitalSignatureAlgorithm as ECDSA, Message, RecoverableSignature, Signature,
+    },
+    PublicKey,
+};
+
+/// A secp256k1 secret key: an integer `k` with `1 <= k < n`, kept as 32 big endian bytes.
+/// The bytes are wiped from memory when the key is dropped and are never printed; use
+/// [`SecretKey::expose_secret`] to read them explicitly.
+///
+/// Signing and deriving the public key also wipe the byte and integer copies of the key they
+/// make. The field elements the arithmetic runs on are not wiped: the lambdaworks types are
+/// `Copy` and don't implement `Zeroize`, so copies of the key and of the nonces can be left
+/// behind on the stack.
+pub struct SecretKey {
+    bytes: ByteArrayOfLength32,
+}
+
+/// Former name of [`SecretKey`].
+pub type PrivateKey = SecretKey;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SecretKeyError {
+    /// The bytes encode zero or an integer not smaller than the order of the curve.
+    OutOfRange,
+}
+
+/// Errors found while importing a key in Wallet Import Format.
+#[derive(Debug, PartialEq, Eq)]
+pub enum WifError {
+    Base58(Base58Error),
+    /// The decoded payload is neither 33 nor 34 bytes long.
+    InvalidLength(usize),
+    /// The version byte is not the one of any known network.
+    InvalidPrefix(u8),
+    /// The payload has 34 bytes but the last one is not the compression marker 0x01.
+    InvalidCompressionFlag(u8),
+    /// The key is zero or not smaller than the order of the curve.
+    OutOfRange,
+}
+
+impl SecretKey {
+    /// Builds a key from its big endian encoding, which must be an integer in `[1, n)`.
+    pub fn new(bytes: [u8; 32]) -> Result<Self, SecretKeyError> {
+        let key = Self {
+            bytes: ByteArrayOfLength32::new(bytes),
+        };
+        // Checked without branching on the secret: k < n iff k - n borrows
+        let mut integer: U256 = (&key).into();
+        let (_, less_than_order) = U256::sub(&integer, &ScalarFieldModulus::MODULUS);
+        let is_zero = integer
+            .limbs
+            .iter()
+            .fold(0u64, |acc, limb| acc | limb)
+            .ct_eq(&0);
+        integer.limbs.zeroize();
+        if bool::from(Choice::from(less_than_order as u8) & !is_zero) {
+            Ok(key)
+        } else {
+            Err(SecretKeyError::OutOfRange)
+        }
+    }
+
+    /// The big endian encoding of the key. Callers are responsible for not leaking it.
+    pub fn expose_secret(&self) -> &[u8; 32] {
+        &self.bytes.bytes
+    }
+
+    /// Draws a key uniformly at random from `[1, n)`, retrying the out of range draws, which
+    /// happen with probability below 2^-127.
+    pub fn generate<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        loop {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            let key = Self::new(bytes);
+            bytes.zeroize();
+            if let Ok(key) = key {
+                return key;
+            }
+        }
+    }
+
+    /// Generates a fresh key pair with the given random number generator.
+    pub fn generate_keypair<R: RngCore + CryptoRng>(rng: &mut R) -> (Self, PublicKey) {
+        let secret_key = Self::generate(rng);
+        let public_key = secret_key.public_key();
+        (secret_key, public_key)
+    }
+
+    /// Generates a fresh key pair with the random number generator of the operating system.
+    pub fn random_keypair() -> (Self, PublicKey) {
+        Self::generate_keypair(&mut OsRng)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_scalar(&self.scalar())
+    }
+
+    /// Encodes the key in Wallet Import Format: base58check of the network version byte, the
+    /// key and, if the matching public key is to be serialized compressed, a 0x01 marker.
+    pub fn to_wif(&self, chain: Chain, compressed: bool) -> String {
+        let mut data = Vec::with_capacity(34);
+        data.push(chain.parameters().wif_prefix);
+        data.extend_from_slice(self.expose_secret());
+        if compressed {
+            data.push(0x01);
+        }
+        let wif = base58::encode_check(&data);
+        data.zeroize();
+        wif
+    }
+
+    /// Decodes a key in Wallet Import Format, together with its network and whether its
+    /// public key is meant to be serialized compressed. Signet and regtest keys share the prefix
+    /// of testnet and are reported as `TestNet`.
+    pub fn from_wif(wif: &str) -> Result<(Self, Chain, bool), WifError> {
+        let mut data = base58::decode_check(wif)?;
+        let result = Self::from_wif_payload(&data);
+        data.zeroize();
+        result
+    }
+
+    fn from_wif_payload(data: &[u8]) -> Result<(Self, Chain, bool), WifError> {
+        let compressed = match data.len() {
+            33 => false,
+            34 if data[33] == 0x01 => true,
+            34 => return Err(WifError::InvalidCompressionFlag(data[33])),
+            length => return Err(WifError::InvalidLength(length)),
+        };
+        let chain = Chain::from_wif_prefix(data[0]).ok_or(WifError::InvalidPrefix(data[0]))?;
+        let key = Self::new(data[1..33].try_into().unwrap()).map_err(|_| WifError::OutOfRange)?;
+        Ok((key, chain, compressed))
+    }
+
+    /// The key as a scalar, wiping the integer read on the way.
+    pub(crate) fn scalar(&self) -> ScalarFelt {
+        let mut integer: U256 = self.into();
+        let scalar = constant_time::from_u256(&integer);
+        integer.limbs.zeroize();
+        scalar
+    }
+
+    /// Signs `message` with ECDSA. The nonce is derived deterministically from the key and the
+    /// message following RFC 6979, so signing the same message twice yields the same signature.
+    pub fn sign(&self, message: &Message) -> Signature {
+        ECDSA::sign(
+            message,
+            self,
+            &mut DeterministicScalarGenerator::new(self, message, None),
+        )
+    }
+
+    /// Signs `message` like [`PrivateKey::sign`], additionally returning the recovery id that
+    /// allows [`crate::PublicKey::recover`] to compute the signer's public key.
+    pub fn sign_recoverable(&self, message: &Message) -> RecoverableSignature {
+        ECDSA::sign_recoverable(
+            message,
+            self,
+            &mut DeterministicScalarGenerator::new(self, message, None),
+        )
+    }
+
+    /// Same as [`PrivateKey::sign`], but mixes `extra_entropy` into the nonce derivation as
+    /// allowed by section 3.6 of RFC 6979.
+    pub fn sign_with_extra_entropy(
+        &self,
+        message: &Message,
+        extra_entropy: &[u8; 32],
+    ) -> Signature {
+        ECDSA::sign(
+            message,
+            self,
+            &mut DeterministicScalarGenerator::new(self, message, Some(extra_entropy)),
+        )
+    }
+
+    /// Signs `message` with BIP 340 Schnorr, using fresh auxiliary randomness from the thread
+    /// local random generator.
+    pub fn sign_schnorr(&self, message: &[u8]) -> SchnorrSignature {
+        let aux_rand: [u8; 32] = rand::random();
+        self.sign_schnorr_with_aux_rand(message, &aux_rand)
+    }
+
+    /// Signs `message` with BIP 340 Schnorr using the given auxiliary randomness.
+    ///
+    /// # Panics
+    ///
+    /// If the derived nonce is zero, which only happens with negligible probability, or if the
+    /// signature fails the verification that follows signing.
+    pub fn sign_schnorr_with_aux_rand(
+        &self,
+        message: &[u8],
+        aux_rand: &[u8; 32],
+    ) -> SchnorrSignature {
+        SchnorrSignatureAlgorithm::sign(message, self, aux_rand)
+    }
+}
+
+impl From<Base58Error> for WifError {
+    fn from(error: Base58Error) -> Self {
+        Self::Base58(error)
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.bytes.bytes.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretKey(<redacted>)")
+    }
+}
+
+impl From<&SecretKey> for U256 {
+    fn from(private_key: &SecretKey) -> Self {
+        (&private_key.bytes).into()
+    }
+}
+
+impl From<SecretKey> for U256 {
+    fn from(private_key: SecretKey) -> Self {
+        (&private_key).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::{
+        field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
+    };
+
+    use rand::{CryptoRng, RngCore};
+
+    use crate::{
+        address::Chain,
+        base58::{self, Base58Error},
+        private_key::{SecretKey, SecretKeyError, WifError},
+        secp256k1::fields::ScalarFieldModulus,
+        serializer::{CanSerialize, U256BigEndianSerializer},
+        signature::Message,
+        PublicKey,
+    };
+
+    /// Returns the given 32 byte blocks in order, one per call, truncated to the requested length.
+    struct BlockRng {
+        blocks: Vec<[u8; 32]>,
+    }
+
+    impl RngCore for BlockRng {
+        fn next_u32(&mut self) -> u32 {
+            let mut bytes = [0u8; 4];
+            self.fill_bytes(&mut bytes);
+            u32::from_le_bytes(bytes)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut bytes = [0u8; 8];
+            self.fill_bytes(&mut bytes);
+            u64::from_le_bytes(bytes)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.copy_from_slice(&self.blocks.remove(0)[..dest.len()]);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for BlockRng {}
+
+    #[test]
+    fn test_secret_key_range() {
+        let n = ScalarFieldModulus::MODULUS;
+        let encode = |integer: U256| U256BigEndianSerializer::serialize(&integer);
+
+        assert_eq!(
+            SecretKey::new([0u8; 32]).unwrap_err(),
+            SecretKeyError::OutOfRange
+        );
+        assert_eq!(
+            SecretKey::new(encode(n)).unwrap_err(),
+            SecretKeyError::OutOfRange
+        );
+        assert_eq!(
+            SecretKey::new([0xff; 32]).unwrap_err(),
+            SecretKeyError::OutOfRange
+        );
+
+        let one = SecretKey::new(encode(U256::from_u64(1))).unwrap();
+        assert_eq!(one.expose_secret(), &encode(U256::from_u64(1)));
+        let n_minus_one = SecretKey::new(encode(n - U256::from_u64(1))).unwrap();
+        assert_eq!(n_minus_one.expose_secret(), &encode(n - U256::from_u64(1)));
+    }
+
+    #[test]
+    fn test_secret_key_debug_is_redacted() {
+        let secret_key = SecretKey::new([0x42; 32]).unwrap();
+        let debug = format!("{:?}", secret_key);
+        assert_eq!(debug, "SecretKey(<redacted>)");
+        assert!(!debug.contains("42"));
+    }
+
+    #[test]
+    fn test_generate_rejects_out_of_range_values() {
+        let n = U256BigEndianSerializer::serialize(&ScalarFieldModulus::MODULUS);
+        let mut rng = BlockRng {
+            blocks: vec![[0u8; 32], n, [0xff; 32], [7u8; 32], [8u8; 32]],
+        };
+
+        let secret_key = SecretKey::generate(&mut rng);
+        assert_eq!(secret_key.expose_secret(), &[7u8; 32]);
+        assert_eq!(rng.blocks, vec![[8u8; 32]]);
+    }
+
+    #[test]
+    fn test_random_keypair() {
+        let (secret_key, public_key) = SecretKey::random_keypair();
+        let (other_secret_key, _) = SecretKey::random_keypair();
+        assert_ne!(secret_key.expose_secret(), other_secret_key.expose_secret());
+
+        let message = Message::new([3u8; 32]);
+        let signature = secret_key.sign(&message);
+        assert!(public_key.verify(&message, &signature));
+        assert_eq!(PublicKey::from_private_key(secret_key), public_key);
+    }
+
+    fn secret_key_from_u256(integer: U256) -> SecretKey {
+        SecretKey::new(U256BigEndianSerializer::serialize(&integer)).unwrap()
+    }
+
+    #[test]
+    fn test_wif_round_trip() {
+        let vectors = [
+            (
+                U256::from_u64(5003),
+                Chain::TestNet,
+                true,
+                "cMahea7zqjxrtgAbB7LSGbcQUr1uX1ojuat9jZodMN8rFTv2sfUK",
+            ),
+            (
+                U256::from_u64(33715652388894101), // 2021^5
+                Chain::TestNet,
+                false,
+                "91avARGdfge8E4tZfYLoxeJ5sGBdNJQH4kvjpWAxgzczjbCwxic",
+            ),
+            (
+                U256::from_u64(0x54321deadbeef),
+                Chain::MainNet,
+                true,
+                "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgiuQJv1h8Ytr2S53a",
+            ),
+            (
+                U256::from_hex_unchecked(
+                    "0c28fca386c7a227600b2fe50b7cae11ec86d3bf1fbe471be89827e19d72aa1d",
+                ),
+                Chain::MainNet,
+                false,
+                "5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTJ",
+            ),
+        ];
+
+        for (integer, chain, compressed, wif) in vectors {
+            let secret_key = secret_key_from_u256(integer);
+            assert_eq!(secret_key.to_wif(chain, compressed), wif);
+
+            let (decoded, decoded_chain, decoded_compressed) = SecretKey::from_wif(wif).unwrap();
+            assert_eq!(decoded.expose_secret(), secret_key.expose_secret());
+            assert_eq!(decoded_chain, chain);
+            assert_eq!(decoded_compressed, compressed);
+        }
+
+        // Signet and regtest keys are encoded like testnet ones
+        let secret_key = secret_key_from_u256(U256::from_u64(5003));
+        for chain in [Chain::Signet, Chain::Regtest] {
+            let wif = secret_key.to_wif(chain, true);
+            assert_eq!(wif, "cMahea7zqjxrtgAbB7LSGbcQUr1uX1ojuat9jZodMN8rFTv2sfUK");
+            assert_eq!(SecretKey::from_wif(&wif).unwrap().1, Chain::TestNet);
+        }
+    }
+
+    #[test]
+    fn test_wif_errors() {
+        assert_eq!(
+            SecretKey::from_wif("KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgiuQJv1h8Ytr2S53b")
+                .unwrap_err(),
+            WifError::Base58(Base58Error::InvalidChecksum)
+        );
+        assert_eq!(
+            SecretKey::from_wif("KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgiuQJv1h8Ytr2S5Ia")
+                .unwrap_err(),
+            WifError::Base58(Base58Error::InvalidCharacter {
+                character: 'I',
+                position: 50
+            })
+        );
+
+        let encode = |data: &[u8]| base58::encode_check(data);
+        let mut data = vec![0x80];
+        data.extend_from_slice(&[1u8; 32]);
+        data.push(0x02);
+        assert_eq!(
+            SecretKey::from_wif(&encode(&data)).unwrap_err(),
+            WifError::InvalidCompressionFlag(0x02)
+        );
+
+        data[0] = 0x00;
+        data[33] = 0x01;
+        assert_eq!(
+            SecretKey::from_wif(&encode(&data)).unwrap_err(),
+            WifError::InvalidPrefix(0x00)
+        );
+
+        assert_eq!(
+            SecretKey::from_wif(&encode(&data[..20])).unwrap_err(),
+            WifError::InvalidLength(20)
+        );
+
+        let mut data = vec![0x80];
+        data.extend_from_slice(&[0u8; 32]);
+        assert_eq!(
+            SecretKey::from_wif(&encode(&data)).unwrap_err(),
+            WifError::OutOfRange
+        );
+    }
+}
@@ -1,9 +1,12 @@
-use lambdaworks_math::{
-    cyclic_group::IsGroup, elliptic_curve::traits::IsEllipticCurve, unsigned_integer::element::U256,
-};
+#[cfg(test)]
+use lambdaworks_math::unsigned_integer::element::U256;
 
 use crate::{
-    secp256k1::curve::{Point, Secp256k1},
+    schnorr::XOnlyPublicKey,
+    secp256k1::{constant_time, curve::Point, fields::ScalarFelt},
+    signature::{
+        EllipticCurveDigitalSignatureAlgorithm as ECDSA, Message, RecoverableSignature, Signature,
+    },
     PrivateKey,
 };
 
@@ -17,16 +20,42 @@ impl PublicKey {
         Self { point }
     }
 
+    pub(crate) fn from_scalar(scalar: &ScalarFelt) -> Self {
+        Self::new(constant_time::multiply_generator(scalar))
+    }
+
+    #[cfg(test)]
     pub(crate) fn from_u256(integer: U256) -> Self {
-        let point = Secp256k1::generator().operate_with_self(integer);
-        Self::new(point)
+        Self::from_scalar(&constant_time::from_u256(&integer))
     }
 
     pub fn from_private_key(s: PrivateKey) -> Self {
-        Self::from_u256(s.into())
+        s.public_key()
     }
 
     pub(crate) fn point(&self) -> &Point {
         &self.point
     }
+
+    /// The BIP 340 x-only form of this key.
+    pub fn x_only_public_key(&self) -> XOnlyPublicKey {
+        XOnlyPublicKey::from_public_key(self)
+    }
+
+    /// Checks that `signature` is a valid ECDSA signature of `message` under this key.
+    pub fn verify(&self, message: &Message, signature: &Signature) -> bool {
+        ECDSA::verify(message, signature, self)
+    }
+
+    /// Computes the public key that produced `signature` over `message`. Returns `None` if no
+    /// such key exists.
+    pub fn recover(message: &Message, signature: &RecoverableSignature) -> Option<Self> {
+        ECDSA::recover(message, signature)
+    }
+
+    /// Like [`PublicKey::verify`], but also rejects signatures with a high `s` value, which
+    /// Bitcoin nodes refuse to relay (BIP 62 / BIP 146).
+    pub fn verify_strict(&self, message: &Message, signature: &Signature) -> bool {
+        ECDSA::verify_strict(message, signature, self)
+    }
 }
@@ -3,13 +3,27 @@ use lambdaworks_math::{
 };
 
 use crate::{
-    secp256k1::fields::{ScalarFelt, ScalarFieldModulus},
+    byte_array::ByteArrayOfLength32,
+    hash::hmac_sha256,
+    secp256k1::{
+        constant_time,
+        fields::{ScalarFelt, ScalarFieldModulus},
+    },
+    serializer::{CanSerialize, U256BigEndianSerializer},
+    signature::Message,
     PrivateKey,
 };
 use rand::Rng;
+use zeroize::Zeroizing;
 
 pub(crate) struct RandomScalarGenerator;
-pub(crate) struct RandomPrivateKeyGenerator;
+
+/// Nonce generator of RFC 6979 instantiated with HMAC-SHA256. The sequence of scalars it
+/// produces depends only on the private key, the message and the optional extra entropy.
+pub(crate) struct DeterministicScalarGenerator {
+    k: [u8; 32],
+    v: [u8; 32],
+}
 
 pub(crate) trait IsRandomGenerator<T> {
     fn random_scalar(&mut self) -> T;
@@ -35,19 +49,154 @@ impl IsRandomGenerator<ScalarFelt> for RandomScalarGenerator {
     }
 }
 
-impl RandomPrivateKeyGenerator {
-    pub(crate) fn new() -> Self {
-        Self {}
+impl DeterministicScalarGenerator {
+    pub(crate) fn new(
+        private_key: &PrivateKey,
+        message: &Message,
+        extra_entropy: Option<&[u8; 32]>,
+    ) -> Self {
+        // int2octets(x) and bits2octets(h1) of RFC 6979, section 3.2
+        let x = Zeroizing::new(U256BigEndianSerializer::serialize(
+            &constant_time::representative(&private_key.scalar()),
+        ));
+        let h =
+            U256BigEndianSerializer::serialize(&ScalarFelt::new(message.into()).representative());
+
+        let mut generator = Self {
+            k: [0u8; 32],
+            v: [1u8; 32],
+        };
+        for separator in [0u8, 1u8] {
+            let mut data = Zeroizing::new(Vec::with_capacity(32 + 1 + 32 + 32 + 32));
+            data.extend_from_slice(&generator.v);
+            data.push(separator);
+            data.extend_from_slice(&x[..]);
+            data.extend_from_slice(&h);
+            if let Some(extra_entropy) = extra_entropy {
+                data.extend_from_slice(extra_entropy);
+            }
+            generator.k = hmac_sha256(&generator.k, &data);
+            generator.v = hmac_sha256(&generator.k, &generator.v);
+        }
+        generator
+    }
+
+    /// Moves the state forward so that the next call yields a fresh candidate, as RFC 6979
+    /// prescribes both when a candidate is out of range and when the signer rejects it.
+    fn advance(&mut self) {
+        let mut data = Vec::with_capacity(32 + 1);
+        data.extend_from_slice(&self.v);
+        data.push(0);
+        self.k = hmac_sha256(&self.k, &data);
+        self.v = hmac_sha256(&self.k, &self.v);
     }
 }
 
-impl IsRandomGenerator<PrivateKey> for RandomPrivateKeyGenerator {
-    fn random_scalar(&mut self) -> PrivateKey {
-        let mut rng = rand::thread_rng();
-        let mut result = [0u8; 32];
-        for byte in result.iter_mut() {
-            *byte = rng.gen()
+impl IsRandomGenerator<ScalarFelt> for DeterministicScalarGenerator {
+    fn random_scalar(&mut self) -> ScalarFelt {
+        loop {
+            self.v = hmac_sha256(&self.k, &self.v);
+            let candidate: U256 = ByteArrayOfLength32::new(self.v).into();
+            self.advance();
+            if candidate != U256::from_u64(0) && candidate < ScalarFieldModulus::MODULUS {
+                return constant_time::from_u256(&candidate);
+            }
         }
-        PrivateKey::new(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::{
+        field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
+    };
+
+    use crate::{
+        hash::sha256,
+        random::{DeterministicScalarGenerator, IsRandomGenerator},
+        secp256k1::fields::{ScalarFelt, ScalarFieldModulus},
+        serializer::{CanSerialize, U256BigEndianSerializer},
+        signature::Message,
+        PrivateKey,
+    };
+
+    fn private_key_from_u256(integer: U256) -> PrivateKey {
+        PrivateKey::new(U256BigEndianSerializer::serialize(&integer)).unwrap()
+    }
+
+    // Vectors for secp256k1 with SHA-256, as used by Bitcoin Core and python-ecdsa.
+    #[test]
+    fn test_rfc6979_nonce_1() {
+        let private_key = private_key_from_u256(U256::from_u64(1));
+        let message = Message::new(sha256("Satoshi Nakamoto".as_bytes()));
+        let expected_nonce = ScalarFelt::from_hex_unchecked(
+            "8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15",
+        );
+
+        let nonce = DeterministicScalarGenerator::new(&private_key, &message, None).random_scalar();
+        assert_eq!(nonce, expected_nonce);
+    }
+
+    #[test]
+    fn test_rfc6979_nonce_2() {
+        let private_key = private_key_from_u256(U256::from_u64(1));
+        let message = Message::new(sha256(
+            "All those moments will be lost in time, like tears in rain. Time to die...".as_bytes(),
+        ));
+        let expected_nonce = ScalarFelt::from_hex_unchecked(
+            "38aa22d72376b4dbc472e06c3ba403ee0a394da63fc58d88686c611aba98d6b3",
+        );
+
+        let nonce = DeterministicScalarGenerator::new(&private_key, &message, None).random_scalar();
+        assert_eq!(nonce, expected_nonce);
+    }
+
+    #[test]
+    fn test_rfc6979_nonce_3() {
+        let private_key = private_key_from_u256(ScalarFieldModulus::MODULUS - U256::from_u64(1));
+        let message = Message::new(sha256("Satoshi Nakamoto".as_bytes()));
+        let expected_nonce = ScalarFelt::from_hex_unchecked(
+            "33a19b60e25fb6f4435af53a3d42d493644827367e6453928554f43e49aa6f90",
+        );
+
+        let nonce = DeterministicScalarGenerator::new(&private_key, &message, None).random_scalar();
+        assert_eq!(nonce, expected_nonce);
+    }
+
+    #[test]
+    fn test_rfc6979_nonce_4() {
+        let private_key = private_key_from_u256(U256::from_hex_unchecked(
+            "f8b8af8ce3c7cca5e300d33939540c10d45ce001b8f252bfbc57ba0342904181",
+        ));
+        let message = Message::new(sha256("Alan Turing".as_bytes()));
+        let expected_nonce = ScalarFelt::from_hex_unchecked(
+            "525a82b70e67874398067543fd84c83d30c175fdc45fdeee082fe13b1d7cfdf1",
+        );
+
+        let nonce = DeterministicScalarGenerator::new(&private_key, &message, None).random_scalar();
+        assert_eq!(nonce, expected_nonce);
+    }
+
+    #[test]
+    fn test_rfc6979_extra_entropy_changes_nonce() {
+        let private_key = private_key_from_u256(U256::from_u64(1));
+        let message = Message::new(sha256("Satoshi Nakamoto".as_bytes()));
+
+        let nonce = DeterministicScalarGenerator::new(&private_key, &message, None).random_scalar();
+        let nonce_with_entropy =
+            DeterministicScalarGenerator::new(&private_key, &message, Some(&[1u8; 32]))
+                .random_scalar();
+        assert_ne!(nonce, nonce_with_entropy);
+    }
+
+    #[test]
+    fn test_rfc6979_successive_nonces_differ() {
+        let private_key = private_key_from_u256(U256::from_u64(1));
+        let message = Message::new(sha256("Satoshi Nakamoto".as_bytes()));
+
+        let mut generator = DeterministicScalarGenerator::new(&private_key, &message, None);
+        let first = generator.random_scalar();
+        let second = generator.random_scalar();
+        assert_ne!(first, second);
     }
 }
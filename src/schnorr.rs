@@ -0,0 +1,336 @@
+use lambdaworks_math::{
+    cyclic_group::IsGroup, field::fields::montgomery_backed_prime_fields::IsModulus,
+    unsigned_integer::element::U256,
+};
+use subtle::Choice;
+use zeroize::Zeroizing;
+
+use crate::{
+    byte_array::ByteArrayOfLength32,
+    hash::tagged_hash,
+    secp256k1::{
+        constant_time,
+        curve::{Point, Secp256k1},
+        fields::{BaseFelt, BaseFieldModulus, ScalarFelt, ScalarFieldModulus},
+    },
+    serializer::{CanSerialize, FeltSerializer, ParserError, U256BigEndianSerializer},
+    PrivateKey, PublicKey,
+};
+
+/// A public key given only by its x-coordinate, as used by BIP 340 and Taproot. It stands for
+/// the unique point with that x-coordinate and an even y-coordinate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XOnlyPublicKey {
+    pub(crate) point: Point,
+}
+
+/// A BIP 340 signature: the x-coordinate of the nonce point R and the scalar s.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchnorrSignature {
+    pub(crate) r: BaseFelt,
+    pub(crate) s: ScalarFelt,
+}
+
+pub(crate) struct SchnorrSignatureAlgorithm;
+
+fn has_even_y(point: &Point) -> bool {
+    point.to_affine().y().representative().limbs[3] & 1 == 0
+}
+
+impl XOnlyPublicKey {
+    /// Drops the parity of `public_key`, keeping the point with the same x-coordinate and even
+    /// y-coordinate.
+    pub fn from_public_key(public_key: &PublicKey) -> Self {
+        Self::from_point(public_key.point())
+    }
+
+    fn from_point(point: &Point) -> Self {
+        let point = point.to_affine();
+        if has_even_y(&point) {
+            Self { point }
+        } else {
+            Self { point: point.neg() }
+        }
+    }
+
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, ParserError> {
+        let x: U256 = ByteArrayOfLength32::new(*bytes).into();
+        if x >= BaseFieldModulus::MODULUS {
+            return Err(ParserError::ParseError);
+        }
+        let point = Secp256k1::lift_x(&BaseFelt::new(x), false).ok_or(ParserError::ParseError)?;
+        Ok(Self { point })
+    }
+
+    pub fn to_bytes(&self) -> [u8; 32] {
+        FeltSerializer::serialize(self.point.to_affine().x())
+    }
+
+    /// Taproot output key of BIP 341: this internal key plus `t G`, where `t` is the tagged hash
+    /// of the key and the merkle root of the script tree, if there is one.
+    ///
+    /// # Panics
+    ///
+    /// If `t` is not below the group order or the output key is the point at infinity, which
+    /// only happens with negligible probability.
+    pub fn tap_tweak(&self, merkle_root: Option<&[u8; 32]>) -> Self {
+        let mut data = self.to_bytes().to_vec();
+        if let Some(merkle_root) = merkle_root {
+            data.extend_from_slice(merkle_root);
+        }
+        let t: U256 = ByteArrayOfLength32::new(tagged_hash("TapTweak", &data)).into();
+        assert!(t < ScalarFieldModulus::MODULUS, "invalid TapTweak");
+        let output_point = self.point.operate_with(&Secp256k1::multiply_generator(t));
+        assert!(!output_point.is_neutral_element(), "invalid TapTweak");
+        Self::from_point(&output_point)
+    }
+
+    /// Checks that `signature` is a valid BIP 340 signature of `message` under this key.
+    pub fn verify(&self, message: &[u8], signature: &SchnorrSignature) -> bool {
+        SchnorrSignatureAlgorithm::verify(message, signature, self)
+    }
+}
+
+impl SchnorrSignature {
+    pub(crate) fn new(r: BaseFelt, s: ScalarFelt) -> Self {
+        Self { r, s }
+    }
+
+    /// Parses the 64 byte encoding `r || s`, rejecting `r >= p` and `s >= n`.
+    pub fn from_bytes(bytes: &[u8; 64]) -> Result<Self, ParserError> {
+        let r: U256 = ByteArrayOfLength32::new(bytes[..32].try_into().unwrap()).into();
+        let s: U256 = ByteArrayOfLength32::new(bytes[32..].try_into().unwrap()).into();
+        if r >= BaseFieldModulus::MODULUS || s >= ScalarFieldModulus::MODULUS {
+            return Err(ParserError::ParseError);
+        }
+        Ok(Self::new(BaseFelt::new(r), ScalarFelt::new(s)))
+    }
+
+    pub fn to_bytes(&self) -> [u8; 64] {
+        let mut result = [0u8; 64];
+        result[..32].copy_from_slice(&FeltSerializer::serialize(&self.r));
+        result[32..].copy_from_slice(&FeltSerializer::serialize(&self.s));
+        result
+    }
+}
+
+impl SchnorrSignatureAlgorithm {
+    pub(crate) fn challenge(r: &[u8; 32], public_key: &[u8; 32], message: &[u8]) -> ScalarFelt {
+        let hash = tagged_hash("BIP0340/challenge", &[r, public_key, message].concat());
+        ScalarFelt::new(ByteArrayOfLength32::new(hash).into())
+    }
+
+    /// # Panics
+    ///
+    /// If the derived nonce is zero, which only happens with negligible probability, or if the
+    /// signature doesn't verify, which would point to a fault during signing.
+    pub(crate) fn sign(
+        message: &[u8],
+        private_key: &PrivateKey,
+        aux_rand: &[u8; 32],
+    ) -> SchnorrSignature {
+        let d = private_key.scalar();
+        let public_point = constant_time::to_affine(&constant_time::multiply_generator(&d));
+        let d =
+            constant_time::conditional_negate(&d, Choice::from(!has_even_y(&public_point) as u8));
+        let public_key = FeltSerializer::serialize(public_point.x());
+
+        // Masking the key with the hashed auxiliary randomness protects against side channel
+        // attacks on the nonce derivation, while the nonce stays safe if the randomness is bad.
+        let mut t = Zeroizing::new(U256BigEndianSerializer::serialize(
+            &constant_time::representative(&d),
+        ));
+        for (byte, mask) in t.iter_mut().zip(tagged_hash("BIP0340/aux", aux_rand)) {
+            *byte ^= mask;
+        }
+        let nonce_data = Zeroizing::new([&t[..], &public_key, message].concat());
+        let nonce_hash = tagged_hash("BIP0340/nonce", &nonce_data);
+        let k = constant_time::from_u256(&ByteArrayOfLength32::new(nonce_hash).into());
+        assert!(k != ScalarFelt::zero(), "invalid BIP 340 nonce");
+
+        let nonce_point = constant_time::to_affine(&constant_time::multiply_generator(&k));
+        let k =
+            constant_time::conditional_negate(&k, Choice::from(!has_even_y(&nonce_point) as u8));
+        let r = FeltSerializer::serialize(nonce_point.x());
+
+        let e = Self::challenge(&r, &public_key, message);
+        let s = constant_time::add(&k, &constant_time::mul(&e, &d));
+        let signature = SchnorrSignature::new(nonce_point.x().clone(), s);
+
+        // BIP 340 recommends this check to keep a fault during signing from leaking the key.
+        let x_only_public_key = XOnlyPublicKey::from_point(&public_point);
+        assert!(
+            Self::verify(message, &signature, &x_only_public_key),
+            "BIP 340 signature doesn't verify"
+        );
+        signature
+    }
+
+    pub(crate) fn verify(
+        message: &[u8],
+        signature: &SchnorrSignature,
+        public_key: &XOnlyPublicKey,
+    ) -> bool {
+        let r = FeltSerializer::serialize(&signature.r);
+        let e = Self::challenge(&r, &public_key.to_bytes(), message);
+        let point = Secp256k1::multiply_generator_and_add(&signature.s, &-e, &public_key.point);
+        if point.is_neutral_element() {
+            return false;
+        }
+        let point = point.to_affine();
+        has_even_y(&point) && point.x() == &signature.r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        schnorr::{SchnorrSignature, SchnorrSignatureAlgorithm, XOnlyPublicKey},
+        transaction::decode_hex,
+        PrivateKey, PublicKey,
+    };
+
+    /// `test-vectors.csv` from BIP 340.
+    const TEST_VECTORS: &str = "\
+index,secret key,public key,aux_rand,message,signature,verification result,comment
+0,0000000000000000000000000000000000000000000000000000000000000003,F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9,0000000000000000000000000000000000000000000000000000000000000000,0000000000000000000000000000000000000000000000000000000000000000,E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0,TRUE,
+1,B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,0000000000000000000000000000000000000000000000000000000000000001,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A,TRUE,
+2,C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9,DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8,C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906,7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C,5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7,TRUE,
+3,0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710,25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF,7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3,TRUE,test fails if msg is reduced modulo p or n
+4,,D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9,,4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703,00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4,TRUE,
+5,,EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,public key not on the curve
+6,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2,FALSE,has_even_y(R) is false
+7,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD,FALSE,negated message
+8,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6,FALSE,negated s value
+9,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051,FALSE,sG - eP is infinite. Test fails in single verification if has_even_y(inf) is defined as true and x(inf) as 0
+10,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197,FALSE,sG - eP is infinite. Test fails in single verification if has_even_y(inf) is defined as true and x(inf) as 1
+11,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,sig[0:32] is not an X coordinate on the curve
+12,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,sig[0:32] is equal to field size
+13,,DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141,FALSE,sig[32:64] is equal to curve order
+14,,FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30,,243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89,6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B,FALSE,public key is not a valid X coordinate because it exceeds the field size
+15,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,,71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63,TRUE,message of size 0 (added 2022-12)
+16,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,11,08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF,TRUE,message of size 1 (added 2022-12)
+17,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,0102030405060708090A0B0C0D0E0F1011,5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5,TRUE,message of size 17 (added 2022-12)
+18,0340034003400340034003400340034003400340034003400340034003400340,778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117,0000000000000000000000000000000000000000000000000000000000000000,99999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999,403B12B0D8555A344175EA7EC746566303321E5DBFA8BE6F091635163ECA79A8585ED3E3170807E7C03B720FC54C7B23897FCBA0E9D0B4A06894CFD249F22367,TRUE,message of size 100 (added 2022-12)
+";
+
+    #[test]
+    fn test_bip340_vectors() {
+        for line in TEST_VECTORS.lines().skip(1) {
+            let fields: Vec<&str> = line.split(',').collect();
+            let public_key_bytes: [u8; 32] = decode_hex(fields[2]).unwrap().try_into().unwrap();
+            let message = decode_hex(fields[4]).unwrap();
+            let signature_bytes: [u8; 64] = decode_hex(fields[5]).unwrap().try_into().unwrap();
+            let expected_result = fields[6] == "TRUE";
+
+            if !fields[1].is_empty() {
+                let private_key =
+                    PrivateKey::new(decode_hex(fields[1]).unwrap().try_into().unwrap()).unwrap();
+                let aux_rand: [u8; 32] = decode_hex(fields[3]).unwrap().try_into().unwrap();
+
+                let signature = SchnorrSignatureAlgorithm::sign(&message, &private_key, &aux_rand);
+                assert_eq!(
+                    signature.to_bytes(),
+                    signature_bytes,
+                    "vector {}",
+                    fields[0]
+                );
+
+                let public_key =
+                    XOnlyPublicKey::from_public_key(&PublicKey::from_private_key(private_key));
+                assert_eq!(
+                    public_key.to_bytes(),
+                    public_key_bytes,
+                    "vector {}",
+                    fields[0]
+                );
+            }
+
+            let result = match (
+                XOnlyPublicKey::from_bytes(&public_key_bytes),
+                SchnorrSignature::from_bytes(&signature_bytes),
+            ) {
+                (Ok(public_key), Ok(signature)) => public_key.verify(&message, &signature),
+                _ => false,
+            };
+            assert_eq!(result, expected_result, "vector {}", fields[0]);
+        }
+    }
+
+    #[test]
+    fn test_schnorr_signature_rejects_out_of_range_values() {
+        let mut bytes = [0u8; 64];
+        // r = p
+        bytes[..32].copy_from_slice(
+            &decode_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F")
+                .unwrap(),
+        );
+        assert!(SchnorrSignature::from_bytes(&bytes).is_err());
+
+        // s = n
+        let mut bytes = [0u8; 64];
+        bytes[32..].copy_from_slice(
+            &decode_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141")
+                .unwrap(),
+        );
+        assert!(SchnorrSignature::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_x_only_public_key_rejects_field_overflow() {
+        // x = p
+        let bytes: [u8; 32] =
+            decode_hex("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        assert!(XOnlyPublicKey::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_schnorr_sign_and_verify_with_public_api() {
+        let private_key = PrivateKey::new([7u8; 32]).unwrap();
+        let message = "Programming Bitcoin!".as_bytes();
+
+        let signature = private_key.sign_schnorr(message);
+        let public_key = PublicKey::from_private_key(private_key).x_only_public_key();
+
+        assert!(public_key.verify(message, &signature));
+        assert!(!public_key.verify("Programming Bitcoin?".as_bytes(), &signature));
+    }
+
+    /// Key path only outputs from BIP 86 and an output with a script tree from the wallet test
+    /// vectors of BIP 341.
+    #[test]
+    fn test_tap_tweak() {
+        let vectors = [
+            (
+                "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115",
+                None,
+                "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+            ),
+            (
+                "d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d",
+                None,
+                "53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343",
+            ),
+            (
+                "187791b6f712a8ea41c8ecdd0ee77fab3e85263b37e1ec18a3651926b3a6cf27",
+                Some("5b75adecf53548f3ec6ad7d78383bf84cc57b55a3127c72b9a2481752dd88b21"),
+                "147c9c57132f6e7ecddba9800bb0c4449251c92a1e60371ee77557b6620f3ea3",
+            ),
+        ];
+        for (internal_key, merkle_root, expected_output_key) in vectors {
+            let internal_key =
+                XOnlyPublicKey::from_bytes(&decode_hex(internal_key).unwrap().try_into().unwrap())
+                    .unwrap();
+            let merkle_root: Option<[u8; 32]> =
+                merkle_root.map(|root| decode_hex(root).unwrap().try_into().unwrap());
+            let output_key = internal_key.tap_tweak(merkle_root.as_ref());
+            assert_eq!(
+                output_key.to_bytes().to_vec(),
+                decode_hex(expected_output_key).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,400 @@
+//! Operations on secret values whose running time and memory access pattern do not depend on
+//! those values. The arithmetic of lambdaworks skips reductions and takes shortcuts for zero,
+//! so field elements are handled here through their Montgomery form with branch-free
+//! reimplementations of addition, subtraction and multiplication.
+
+use std::fmt::Debug;
+
+use lambdaworks_math::{
+    cyclic_group::IsGroup,
+    field::{
+        element::FieldElement,
+        fields::montgomery_backed_prime_fields::{IsModulus, MontgomeryBackendPrimeField},
+    },
+    unsigned_integer::element::U256,
+};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+
+use super::{
+    curve::{Point, Secp256k1},
+    fields::{BaseFelt, ScalarFelt},
+};
+
+type MontgomeryField<M> = MontgomeryBackendPrimeField<M, 4>;
+type MontgomeryFelt<M> = FieldElement<MontgomeryField<M>>;
+
+fn select_u256(a: &U256, b: &U256, choice: Choice) -> U256 {
+    U256::from_limbs(std::array::from_fn(|i| {
+        u64::conditional_select(&a.limbs[i], &b.limbs[i], choice)
+    }))
+}
+
+/// Returns `b` if `choice` is set and `a` otherwise.
+fn select<M>(a: &MontgomeryFelt<M>, b: &MontgomeryFelt<M>, choice: Choice) -> MontgomeryFelt<M>
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    FieldElement::from_raw(select_u256(a.value(), b.value(), choice))
+}
+
+fn select_point(a: &Point, b: &Point, choice: Choice) -> Point {
+    let [x1, y1, z1] = a.coordinates();
+    let [x2, y2, z2] = b.coordinates();
+    Point::new([
+        select(x1, x2, choice),
+        select(y1, y2, choice),
+        select(z1, z2, choice),
+    ])
+}
+
+/// Subtracts the modulus from `value` unless that underflows, and the high bit `overflow` of
+/// `value` is not set. Brings values in `[0, 2m)` to `[0, m)`.
+fn reduce_once<M: IsModulus<U256>>(value: &U256, overflow: bool) -> U256 {
+    let (reduced, borrow) = U256::sub(value, &M::MODULUS);
+    let keep = Choice::from(borrow as u8) & !Choice::from(overflow as u8);
+    select_u256(&reduced, value, keep)
+}
+
+pub(crate) fn add<M>(a: &MontgomeryFelt<M>, b: &MontgomeryFelt<M>) -> MontgomeryFelt<M>
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    let (sum, overflow) = U256::add(a.value(), b.value());
+    FieldElement::from_raw(reduce_once::<M>(&sum, overflow))
+}
+
+pub(crate) fn sub<M>(a: &MontgomeryFelt<M>, b: &MontgomeryFelt<M>) -> MontgomeryFelt<M>
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    let (difference, borrow) = U256::sub(a.value(), b.value());
+    let (corrected, _) = U256::add(&difference, &M::MODULUS);
+    FieldElement::from_raw(select_u256(
+        &difference,
+        &corrected,
+        Choice::from(borrow as u8),
+    ))
+}
+
+/// Montgomery product `a * b / 2^256 mod m` of raw values, with the CIOS method. This is the
+/// algorithm used by lambdaworks, with a masked final subtraction.
+fn montgomery_product<M>(a: &U256, b: &U256) -> U256
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    let q = &M::MODULUS;
+    let mu = MontgomeryField::<M>::MU as u128;
+    let mut t = [0u64; 4];
+    let mut t_extra = [0u64; 2];
+    for i in (0..4).rev() {
+        let mut c: u128 = 0;
+        for j in (0..4).rev() {
+            let cs = t[j] as u128 + (a.limbs[j] as u128) * (b.limbs[i] as u128) + c;
+            c = cs >> 64;
+            t[j] = cs as u64;
+        }
+        let cs = t_extra[1] as u128 + c;
+        t_extra[0] = (cs >> 64) as u64;
+        t_extra[1] = cs as u64;
+
+        let m = (t[3] as u128 * mu) as u64 as u128;
+        let mut c = (t[3] as u128 + m * q.limbs[3] as u128) >> 64;
+        for j in (0..3).rev() {
+            let cs = t[j] as u128 + m * (q.limbs[j] as u128) + c;
+            c = cs >> 64;
+            t[j + 1] = cs as u64;
+        }
+        let cs = t_extra[1] as u128 + c;
+        t[0] = cs as u64;
+        t_extra[1] = t_extra[0] + (cs >> 64) as u64;
+    }
+    reduce_once::<M>(&U256::from_limbs(t), t_extra[1] > 0)
+}
+
+pub(crate) fn mul<M>(a: &MontgomeryFelt<M>, b: &MontgomeryFelt<M>) -> MontgomeryFelt<M>
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    FieldElement::from_raw(montgomery_product::<M>(a.value(), b.value()))
+}
+
+/// Same as `FieldElement::new`, reducing `value` modulo m.
+pub(crate) fn from_u256<M>(value: &U256) -> MontgomeryFelt<M>
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    FieldElement::from_raw(montgomery_product::<M>(value, &MontgomeryField::<M>::R2))
+}
+
+/// Same as `FieldElement::representative`.
+pub(crate) fn representative<M>(x: &MontgomeryFelt<M>) -> U256
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    montgomery_product::<M>(x.value(), &U256::from_u64(1))
+}
+
+/// Returns `-x` if `choice` is set and `x` otherwise.
+pub(crate) fn conditional_negate(x: &ScalarFelt, choice: Choice) -> ScalarFelt {
+    select(x, &sub(&ScalarFelt::zero(), x), choice)
+}
+
+/// Computes `x^-1` as `x^(m - 2)` by square and multiply. Only the public exponent decides
+/// which operations are performed.
+pub(crate) fn invert<M>(x: &MontgomeryFelt<M>) -> MontgomeryFelt<M>
+where
+    M: IsModulus<U256> + Clone + Debug,
+{
+    let exponent = M::MODULUS - U256::from_u64(2);
+    let mut result = MontgomeryFelt::<M>::one();
+    for i in (0..256).rev() {
+        result = mul(&result, &result);
+        if (exponent.limbs[3 - i / 64] >> (i % 64)) & 1 == 1 {
+            result = mul(&result, x);
+        }
+    }
+    result
+}
+
+/// Adds two points with the complete formulas of Renes, Costello and Batina (algorithm 7 of
+/// "Complete addition formulas for prime order elliptic curves", for `a = 0`). They are valid
+/// for every pair of inputs, including doublings and the point at infinity, so there are no
+/// special cases to branch on.
+fn complete_addition(p: &Point, q: &Point) -> Point {
+    let b3 = BaseFelt::from(21);
+    let [x1, y1, z1] = p.coordinates();
+    let [x2, y2, z2] = q.coordinates();
+
+    let t0 = mul(x1, x2);
+    let t1 = mul(y1, y2);
+    let t2 = mul(z1, z2);
+    let t3 = sub(&mul(&add(x1, y1), &add(x2, y2)), &add(&t0, &t1));
+    let t4 = sub(&mul(&add(y1, z1), &add(y2, z2)), &add(&t1, &t2));
+    let y3 = sub(&mul(&add(x1, z1), &add(x2, z2)), &add(&t0, &t2));
+    let t0 = add(&add(&t0, &t0), &t0);
+    let t2 = mul(&b3, &t2);
+    let z3 = add(&t1, &t2);
+    let t1 = sub(&t1, &t2);
+    let y3 = mul(&b3, &y3);
+
+    let x3 = sub(&mul(&t3, &t1), &mul(&t4, &y3));
+    let y3 = add(&mul(&y3, &t0), &mul(&t1, &z3));
+    let z3 = add(&mul(&z3, &t4), &mul(&t0, &t3));
+    Point::new([x3, y3, z3])
+}
+
+/// Returns `index * base`, reading every candidate `entries[j] = (j + 1) * base` so that the
+/// memory access pattern does not depend on `index`.
+fn lookup<'a>(entries: impl Iterator<Item = &'a Point>, index: u8) -> Point {
+    entries
+        .enumerate()
+        .fold(Point::neutral_element(), |acc, (j, entry)| {
+            select_point(&acc, entry, index.ct_eq(&(j as u8 + 1)))
+        })
+}
+
+/// Computes `scalar * G` with fixed 4 bit windows over the table of multiples of the generator.
+/// Every window performs the same masked lookup and complete addition, whatever its value.
+pub(crate) fn multiply_generator(scalar: &ScalarFelt) -> Point {
+    let table = Secp256k1::generator_table();
+    let bytes = representative(scalar)
+        .limbs
+        .into_iter()
+        .rev()
+        .flat_map(|limb| limb.to_le_bytes());
+
+    let mut result = Point::neutral_element();
+    for (byte, row) in bytes.zip(table) {
+        // The row holds (j + 1) * 256^i * G, so the multiples of 16 * 256^i * G are every
+        // sixteenth entry
+        let low = lookup(row[..15].iter(), byte & 0x0f);
+        let high = lookup(row.iter().skip(15).step_by(16), byte >> 4);
+        result = complete_addition(&result, &complete_addition(&low, &high));
+    }
+    result
+}
+
+/// Returns the affine form of `point`, computing the inverse of its z-coordinate in constant
+/// time. `point` must not be the point at infinity.
+pub(crate) fn to_affine(point: &Point) -> Point {
+    let [x, y, z] = point.coordinates();
+    let z_inv = invert(z);
+    Point::new([mul(x, &z_inv), mul(y, &z_inv), BaseFelt::one()])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use lambdaworks_math::{
+        cyclic_group::IsGroup, elliptic_curve::traits::IsEllipticCurve,
+        unsigned_integer::element::U256,
+    };
+    use subtle::Choice;
+
+    use crate::{
+        random::{IsRandomGenerator, RandomScalarGenerator},
+        secp256k1::{
+            constant_time::{
+                add, complete_addition, conditional_negate, from_u256, invert, mul,
+                multiply_generator, representative, sub, to_affine,
+            },
+            curve::{Point, Secp256k1},
+            fields::{BaseFelt, ScalarFelt, ScalarFieldModulus},
+        },
+        signature::Message,
+        PrivateKey, PublicKey,
+    };
+
+    #[test]
+    fn test_field_operations() {
+        let mut generator = RandomScalarGenerator::new();
+        let mut scalars = vec![ScalarFelt::zero(), ScalarFelt::one(), -ScalarFelt::one()];
+        scalars.extend((0..10).map(|_| generator.random_scalar()));
+
+        for a in &scalars {
+            for b in &scalars {
+                assert_eq!(add(a, b), a + b);
+                assert_eq!(sub(a, b), a - b);
+                assert_eq!(mul(a, b), a * b);
+            }
+            assert_eq!(representative(a), a.representative());
+        }
+
+        let p_minus_one = BaseFelt::zero() - BaseFelt::one();
+        assert_eq!(mul(&p_minus_one, &p_minus_one), BaseFelt::one());
+        assert_eq!(add(&p_minus_one, &p_minus_one), -BaseFelt::from(2));
+
+        let max = U256::from_limbs([u64::MAX; 4]);
+        assert_eq!(from_u256::<ScalarFieldModulus>(&max), ScalarFelt::new(max));
+    }
+
+    #[test]
+    fn test_complete_addition() {
+        let mut generator = RandomScalarGenerator::new();
+        let p =
+            Secp256k1::generator().operate_with_self(generator.random_scalar().representative());
+        let q =
+            Secp256k1::generator().operate_with_self(generator.random_scalar().representative());
+        let neutral = Point::neutral_element();
+
+        assert_eq!(complete_addition(&p, &q), p.operate_with(&q));
+        assert_eq!(complete_addition(&p, &p), p.operate_with(&p));
+        assert_eq!(complete_addition(&p, &neutral), p);
+        assert_eq!(complete_addition(&neutral, &q), q);
+        assert!(complete_addition(&p, &p.neg()).is_neutral_element());
+        assert!(complete_addition(&neutral, &neutral).is_neutral_element());
+    }
+
+    #[test]
+    fn test_multiply_generator() {
+        let mut generator = RandomScalarGenerator::new();
+        let mut scalars = vec![
+            ScalarFelt::zero(),
+            ScalarFelt::one(),
+            -ScalarFelt::one(),
+            ScalarFelt::from(0xf0f0),
+        ];
+        scalars.extend((0..10).map(|_| generator.random_scalar()));
+
+        for scalar in scalars {
+            assert_eq!(
+                multiply_generator(&scalar),
+                Secp256k1::generator().operate_with_self(scalar.representative())
+            );
+        }
+    }
+
+    #[test]
+    fn test_invert() {
+        let mut generator = RandomScalarGenerator::new();
+        for _ in 0..10 {
+            let scalar = generator.random_scalar();
+            assert_eq!(invert(&scalar), scalar.inv().unwrap());
+        }
+        let x = BaseFelt::from(7);
+        assert_eq!(invert(&x) * x, BaseFelt::one());
+    }
+
+    #[test]
+    fn test_conditional_negate_and_to_affine() {
+        let x = ScalarFelt::from(5);
+        assert_eq!(conditional_negate(&x, Choice::from(0)), x);
+        assert_eq!(conditional_negate(&x, Choice::from(1)), -x);
+
+        let point = Secp256k1::generator().operate_with_self(U256::from_u64(12345));
+        let affine = to_affine(&point);
+        assert_eq!(affine.coordinates(), point.to_affine().coordinates());
+    }
+
+    /// Welch's t statistic of two samples of timings, after discarding the slowest 10% of
+    /// each, which are dominated by interruptions of the process.
+    fn welch_t_statistic(mut a: Vec<f64>, mut b: Vec<f64>) -> f64 {
+        let crop = |v: &mut Vec<f64>| {
+            v.sort_by(|x, y| x.partial_cmp(y).unwrap());
+            v.truncate(v.len() * 9 / 10);
+        };
+        crop(&mut a);
+        crop(&mut b);
+        let mean_and_variance = |v: &[f64]| {
+            let mean = v.iter().sum::<f64>() / v.len() as f64;
+            let variance = v.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (v.len() - 1) as f64;
+            (mean, variance)
+        };
+        let (mean_a, variance_a) = mean_and_variance(&a);
+        let (mean_b, variance_b) = mean_and_variance(&b);
+        (mean_a - mean_b) / (variance_a / a.len() as f64 + variance_b / b.len() as f64).sqrt()
+    }
+
+    /// Statistical timing test in the style of dudect: `operation` is timed with a fixed key
+    /// made mostly of zero bytes and with random keys, interleaving both classes in a random
+    /// order, and the t statistic of the two samples is returned. A |t| above 4.5 is strong
+    /// evidence that the running time depends on the key.
+    fn key_dependence_t_statistic(operation: impl Fn(PrivateKey)) -> f64 {
+        let mut fixed_key = [0u8; 32];
+        fixed_key[31] = 1;
+        let mut fixed_timings = Vec::new();
+        let mut random_timings = Vec::new();
+
+        for _ in 0..20000 {
+            let use_fixed_key: bool = rand::random();
+            let private_key = if use_fixed_key {
+                PrivateKey::new(fixed_key).unwrap()
+            } else {
+                PrivateKey::new(rand::random()).unwrap()
+            };
+            let start = Instant::now();
+            operation(private_key);
+            let elapsed = start.elapsed().as_nanos() as f64;
+
+            if use_fixed_key {
+                fixed_timings.push(elapsed);
+            } else {
+                random_timings.push(elapsed);
+            }
+        }
+        welch_t_statistic(fixed_timings, random_timings)
+    }
+
+    // The timing tests are noisy by nature, so they are not run by default. Use
+    // `cargo test --release -- --ignored does_not_depend_on_key` on an idle machine.
+
+    #[test]
+    #[ignore]
+    fn test_signing_time_does_not_depend_on_key() {
+        let message = Message::new([0xab; 32]);
+        let t = key_dependence_t_statistic(|private_key| {
+            std::hint::black_box(private_key.sign(&message));
+        });
+        assert!(t.abs() < 4.5, "timing depends on the key: t = {t}");
+    }
+
+    #[test]
+    #[ignore]
+    fn test_public_key_derivation_time_does_not_depend_on_key() {
+        let t = key_dependence_t_statistic(|private_key| {
+            std::hint::black_box(PublicKey::from_private_key(private_key));
+        });
+        assert!(t.abs() < 4.5, "timing depends on the key: t = {t}");
+    }
+}
@@ -1,12 +1,20 @@
 use lambdaworks_math::{
+    cyclic_group::IsGroup,
     elliptic_curve::{
         short_weierstrass::{point::ShortWeierstrassProjectivePoint, traits::IsShortWeierstrass},
         traits::{FromAffine, IsEllipticCurve},
     },
     field::element::FieldElement,
+    unsigned_integer::element::U256,
 };
 
-use super::fields::{BaseFelt, BaseField};
+use std::sync::OnceLock;
+
+use super::fields::{BaseFelt, BaseField, ScalarFelt};
+
+/// `GENERATOR_TABLE[i][j]` holds `(j + 1) * 256^i * G`, so that `k * G` is the sum of one
+/// entry per byte of `k`.
+static GENERATOR_TABLE: OnceLock<Vec<Vec<Point>>> = OnceLock::new();
 
 #[derive(Debug, Clone)]
 pub(crate) struct Secp256k1;
@@ -22,6 +30,239 @@ impl Secp256k1 {
     );
 }
 
+/// Width of the windows of the wNAF representation used for variable-base multiplication.
+const WNAF_WIDTH: usize = 5;
+
+/// Constants of the GLV endomorphism `phi(x, y) = (beta * x, y)`, which acts on the curve as
+/// multiplication by `lambda`. The remaining values are those used by libsecp256k1 to split a
+/// scalar `k` as `k1 + k2 * lambda` with `k1` and `k2` of about 128 bits.
+const BETA: BaseFelt = BaseFelt::from_hex_unchecked(
+    "7ae96a2b657c07106e64479eac3434e99cf0497512f58995c1396c28719501ee",
+);
+const LAMBDA: ScalarFelt = ScalarFelt::from_hex_unchecked(
+    "5363ad4cc05c30e0a5261c028812645a122e22ea20816678df02967c1b23bd72",
+);
+const G1: U256 =
+    U256::from_hex_unchecked("3086d221a7d46bcde86c90e49284eb153daa8a1471e8ca7fe893209a45dbb031");
+const G2: U256 =
+    U256::from_hex_unchecked("e4437ed6010e88286f547fa90abfe4c4221208ac9df506c61571b4ae8ac47f71");
+const MINUS_B1: ScalarFelt = ScalarFelt::from_hex_unchecked("e4437ed6010e88286f547fa90abfe4c3");
+const MINUS_B2: ScalarFelt = ScalarFelt::from_hex_unchecked(
+    "fffffffffffffffffffffffffffffffe8a280ac50774346dd765cda83db1562c",
+);
+
+/// Returns `round(k * g / 2^384)`.
+fn multiply_and_shift_384(k: &U256, g: &U256) -> U256 {
+    let (hi, _) = U256::mul(k, g);
+    let round = hi.limbs[2] >> 63;
+    (hi >> 128) + U256::from_u64(round)
+}
+
+/// Splits `k` into a signed pair `(k1, k2)`, given as magnitude and sign, such that
+/// `k = k1 + k2 * lambda (mod n)` and both magnitudes fit in 128 bits.
+fn glv_decomposition(k: &ScalarFelt) -> [(U256, bool); 2] {
+    let k_representative = k.representative();
+    let c1 = ScalarFelt::new(multiply_and_shift_384(&k_representative, &G1));
+    let c2 = ScalarFelt::new(multiply_and_shift_384(&k_representative, &G2));
+    let k2 = c1 * MINUS_B1 + c2 * MINUS_B2;
+    let k1 = k - &k2 * LAMBDA;
+    [k1, k2].map(|component| {
+        let negated = -&component;
+        if component.representative() > negated.representative() {
+            (negated.representative(), true)
+        } else {
+            (component.representative(), false)
+        }
+    })
+}
+
+/// Width-w non-adjacent form of `k`, least significant digit first. Every nonzero digit is odd
+/// and smaller than `2^(w - 1)` in absolute value, and is followed by at least `w - 1` zeros.
+fn wnaf(mut k: U256, width: usize) -> Vec<i32> {
+    let modulus = 1i32 << width;
+    let mut digits = Vec::with_capacity(257);
+    while k != U256::from_u64(0) {
+        let mut digit = 0;
+        if k.limbs[3] & 1 == 1 {
+            digit = (k.limbs[3] % modulus as u64) as i32;
+            if digit >= modulus / 2 {
+                digit -= modulus;
+                k = k + U256::from_u64(-digit as u64);
+            } else {
+                k = k - U256::from_u64(digit as u64);
+            }
+        }
+        digits.push(digit);
+        k >>= 1;
+    }
+    digits
+}
+
+/// Returns `[P, 3P, 5P, ..., (2^(w - 1) - 1) P]`.
+fn odd_multiples(point: &Point, width: usize) -> Vec<Point> {
+    let double = point.operate_with(point);
+    let mut multiples = vec![point.clone()];
+    for _ in 1..(1 << (width - 2)) {
+        let next = multiples.last().unwrap().operate_with(&double);
+        multiples.push(next);
+    }
+    multiples
+}
+
+/// The endomorphism `phi(x, y) = (beta * x, y)`, which equals multiplication by `lambda`.
+fn endomorphism(point: &Point) -> Point {
+    let [x, y, z] = point.coordinates();
+    Point::new([x * BETA, y.clone(), z.clone()])
+}
+
+/// Computes `sum(k_i * P_i)` for a few terms with Strauss' method: every scalar is split with
+/// the GLV endomorphism and written in wNAF, and all of them share a single chain of doublings.
+fn glv_linear_combination(terms: &[(&ScalarFelt, &Point)]) -> Point {
+    let mut expansions: Vec<(Vec<i32>, Vec<Point>)> = Vec::with_capacity(2 * terms.len());
+    for (scalar, point) in terms {
+        let [(k1, k1_negative), (k2, k2_negative)] = glv_decomposition(scalar);
+        let multiples = odd_multiples(point, WNAF_WIDTH);
+        let multiples_of_phi = multiples.iter().map(endomorphism);
+
+        let sign = |point: Point, negative: bool| if negative { point.neg() } else { point };
+        expansions.push((
+            wnaf(k1, WNAF_WIDTH),
+            multiples
+                .iter()
+                .map(|p| sign(p.clone(), k1_negative))
+                .collect(),
+        ));
+        expansions.push((
+            wnaf(k2, WNAF_WIDTH),
+            multiples_of_phi.map(|p| sign(p, k2_negative)).collect(),
+        ));
+    }
+
+    let length = expansions.iter().map(|(digits, _)| digits.len()).max();
+    let mut result = Point::neutral_element();
+    for i in (0..length.unwrap_or(0)).rev() {
+        result = result.operate_with(&result);
+        for (digits, multiples) in &expansions {
+            match digits.get(i) {
+                Some(&digit) if digit > 0 => {
+                    result = result.operate_with(&multiples[(digit as usize - 1) / 2]);
+                }
+                Some(&digit) if digit < 0 => {
+                    result = result.operate_with(&multiples[(-digit as usize - 1) / 2].neg());
+                }
+                _ => {}
+            }
+        }
+    }
+    result
+}
+
+impl Secp256k1 {
+    /// Returns the point with x-coordinate `x` and a y-coordinate of the given parity, if `x`
+    /// is the abscissa of some point of the curve.
+    pub(crate) fn lift_x(x: &BaseFelt, odd_y: bool) -> Option<Point> {
+        let (y1, y2) = (x.pow(3u64) + Self::a() * x + Self::b()).sqrt()?;
+        let y = if (y1.representative().limbs[3] & 1 == 1) == odd_y {
+            y1
+        } else {
+            y2
+        };
+        Self::create_point_from_affine(x.clone(), y).ok()
+    }
+
+    pub(crate) fn generator_table() -> &'static [Vec<Point>] {
+        GENERATOR_TABLE.get_or_init(|| {
+            let mut base = Self::generator();
+            (0..32)
+                .map(|_| {
+                    let mut row = Vec::with_capacity(255);
+                    let mut multiple = base.clone();
+                    for _ in 0..255 {
+                        row.push(multiple.to_affine());
+                        multiple = multiple.operate_with(&base);
+                    }
+                    // multiple = 256 * base
+                    base = multiple;
+                    row
+                })
+                .collect()
+        })
+    }
+
+    /// Computes `scalar * G` using a precomputed table of multiples of the generator, which
+    /// costs 32 point additions instead of a full double-and-add. The table is built on first
+    /// use. The running time depends on `scalar`, so secret scalars go through
+    /// [`super::constant_time::multiply_generator`] instead.
+    pub(crate) fn multiply_generator(scalar: U256) -> Point {
+        let table = Self::generator_table();
+        let bytes = scalar
+            .limbs
+            .iter()
+            .rev()
+            .flat_map(|limb| limb.to_le_bytes());
+        bytes
+            .zip(table)
+            .filter(|(byte, _)| *byte != 0)
+            .fold(Point::neutral_element(), |acc, (byte, row)| {
+                acc.operate_with(&row[byte as usize - 1])
+            })
+    }
+
+    /// Computes `u * G + v * point` with a joint double-and-add (Strauss–Shamir), the core of
+    /// signature verification.
+    pub(crate) fn multiply_generator_and_add(
+        u: &ScalarFelt,
+        v: &ScalarFelt,
+        point: &Point,
+    ) -> Point {
+        glv_linear_combination(&[(u, &Self::generator()), (v, point)])
+    }
+}
+
+/// Returns the integer formed by the bits `start..start + size` of `scalar`.
+fn window(scalar: &U256, start: usize, size: usize) -> usize {
+    (start..(start + size).min(256))
+        .map(|i| ((scalar.limbs[3 - i / 64] >> (i % 64)) & 1) as usize)
+        .rev()
+        .fold(0, |acc, bit| (acc << 1) | bit)
+}
+
+/// Computes `sum(scalars[i] * points[i])` with Pippenger's bucket method. This is much faster
+/// than adding up the individual products when there are many terms.
+pub(crate) fn multi_scalar_multiplication(scalars: &[U256], points: &[Point]) -> Point {
+    assert_eq!(scalars.len(), points.len());
+    // Each window costs one addition per point plus two per bucket
+    let window_size = (1..=16)
+        .min_by_key(|c| 256usize.div_ceil(*c) * (points.len() + (2 << c)))
+        .unwrap();
+    let number_of_windows = 256usize.div_ceil(window_size);
+
+    let mut result = Point::neutral_element();
+    for w in (0..number_of_windows).rev() {
+        for _ in 0..window_size {
+            result = result.operate_with(&result);
+        }
+
+        let mut buckets = vec![Point::neutral_element(); (1 << window_size) - 1];
+        for (scalar, point) in scalars.iter().zip(points) {
+            let digit = window(scalar, w * window_size, window_size);
+            if digit != 0 {
+                buckets[digit - 1] = buckets[digit - 1].operate_with(point);
+            }
+        }
+
+        // sum(j * buckets[j - 1]) computed as a sum of running sums
+        let mut running_sum = Point::neutral_element();
+        let mut window_sum = Point::neutral_element();
+        for bucket in buckets.iter().rev() {
+            running_sum = running_sum.operate_with(bucket);
+            window_sum = window_sum.operate_with(&running_sum);
+        }
+        result = result.operate_with(&window_sum);
+    }
+    result
+}
+
 impl IsEllipticCurve for Secp256k1 {
     type BaseField = BaseField;
     type PointRepresentation = ShortWeierstrassProjectivePoint<Self>;
@@ -48,7 +289,16 @@ pub mod tests {
         field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
     };
 
-    use crate::secp256k1::{curve::Secp256k1, fields::ScalarFieldModulus};
+    use crate::{
+        random::{IsRandomGenerator, RandomScalarGenerator},
+        secp256k1::{
+            curve::{
+                glv_decomposition, glv_linear_combination, multi_scalar_multiplication, wnaf,
+                Point, Secp256k1, LAMBDA, WNAF_WIDTH,
+            },
+            fields::{BaseFelt, ScalarFelt, ScalarFieldModulus},
+        },
+    };
 
     #[test]
     fn test_generator_order() {
@@ -61,4 +311,167 @@ pub mod tests {
             .operate_with_self(expected_order)
             .is_neutral_element())
     }
+
+    #[test]
+    fn test_lift_x() {
+        let generator = Secp256k1::generator().to_affine();
+        let x = generator.x();
+
+        // The y-coordinate of the generator is even
+        assert_eq!(Secp256k1::lift_x(x, false).unwrap(), generator);
+        assert_eq!(Secp256k1::lift_x(x, true).unwrap(), generator.neg());
+
+        // x^3 + 7 is not a square for x = 5
+        assert!(Secp256k1::lift_x(&BaseFelt::from(5), false).is_none());
+    }
+
+    #[test]
+    fn test_multiply_generator() {
+        let mut generator = RandomScalarGenerator::new();
+        let n = ScalarFieldModulus::MODULUS;
+        let mut scalars = vec![
+            U256::from_u64(0),
+            U256::from_u64(1),
+            U256::from_u64(256),
+            n - U256::from_u64(1),
+            n,
+            U256::from_limbs([u64::MAX; 4]),
+        ];
+        scalars.extend((0..10).map(|_| generator.random_scalar().representative()));
+
+        for scalar in scalars {
+            assert_eq!(
+                Secp256k1::multiply_generator(scalar),
+                Secp256k1::generator().operate_with_self(scalar)
+            );
+        }
+    }
+
+    #[test]
+    fn test_multi_scalar_multiplication() {
+        let mut generator = RandomScalarGenerator::new();
+        for size in [0, 1, 2, 7, 40] {
+            let scalars: Vec<U256> = (0..size)
+                .map(|_| generator.random_scalar().representative())
+                .collect();
+            let points: Vec<Point> = (0..size)
+                .map(|_| {
+                    Secp256k1::generator()
+                        .operate_with_self(generator.random_scalar().representative())
+                })
+                .collect();
+
+            let expected = scalars
+                .iter()
+                .zip(&points)
+                .fold(Point::neutral_element(), |acc, (scalar, point)| {
+                    acc.operate_with(&point.operate_with_self(*scalar))
+                });
+            assert_eq!(multi_scalar_multiplication(&scalars, &points), expected);
+        }
+    }
+
+    #[test]
+    fn test_multi_scalar_multiplication_with_extreme_scalars() {
+        let n_minus_one = ScalarFieldModulus::MODULUS - U256::from_u64(1);
+        let scalars = [U256::from_u64(0), U256::from_u64(1), n_minus_one];
+        let points = [
+            Secp256k1::generator(),
+            Secp256k1::generator(),
+            Secp256k1::generator(),
+        ];
+        // 0 * G + 1 * G + (n - 1) * G = n * G
+        assert!(multi_scalar_multiplication(&scalars, &points).is_neutral_element());
+    }
+
+    fn interesting_scalars() -> Vec<ScalarFelt> {
+        let mut generator = RandomScalarGenerator::new();
+        let mut scalars = vec![
+            ScalarFelt::zero(),
+            ScalarFelt::one(),
+            -ScalarFelt::one(),
+            LAMBDA,
+            -LAMBDA,
+            ScalarFelt::from(2).pow(128u64),
+        ];
+        scalars.extend((0..20).map(|_| generator.random_scalar()));
+        scalars
+    }
+
+    #[test]
+    fn test_glv_decomposition() {
+        for k in interesting_scalars() {
+            let [(k1, k1_negative), (k2, k2_negative)] = glv_decomposition(&k);
+            assert!(k1 < U256::from_u64(1) << 128);
+            assert!(k2 < U256::from_u64(1) << 128);
+
+            let k1 = if k1_negative {
+                -ScalarFelt::new(k1)
+            } else {
+                ScalarFelt::new(k1)
+            };
+            let k2 = if k2_negative {
+                -ScalarFelt::new(k2)
+            } else {
+                ScalarFelt::new(k2)
+            };
+            assert_eq!(k1 + k2 * LAMBDA, k);
+        }
+    }
+
+    #[test]
+    fn test_wnaf() {
+        for k in interesting_scalars() {
+            let digits = wnaf(k.representative(), WNAF_WIDTH);
+            let mut value = ScalarFelt::zero();
+            for digit in digits.iter().rev() {
+                value = &value + &value;
+                if *digit >= 0 {
+                    value += ScalarFelt::from(*digit as u64);
+                } else {
+                    value = value - ScalarFelt::from(-digit as u64);
+                }
+            }
+            assert_eq!(value, k);
+            assert!(digits
+                .windows(WNAF_WIDTH)
+                .all(|window| window[1..].iter().all(|d| *d == 0) || window[0] == 0));
+        }
+    }
+
+    #[test]
+    fn test_glv_linear_combination() {
+        let mut generator = RandomScalarGenerator::new();
+        let point =
+            Secp256k1::generator().operate_with_self(generator.random_scalar().representative());
+        for k in interesting_scalars() {
+            assert_eq!(
+                glv_linear_combination(&[(&k, &point)]),
+                point.operate_with_self(k.representative())
+            );
+        }
+    }
+
+    #[test]
+    fn test_multiply_generator_and_add() {
+        let mut generator = RandomScalarGenerator::new();
+        let point =
+            Secp256k1::generator().operate_with_self(generator.random_scalar().representative());
+        let scalars = interesting_scalars();
+        for (u, v) in scalars.iter().zip(scalars.iter().rev()) {
+            let expected = Secp256k1::generator()
+                .operate_with_self(u.representative())
+                .operate_with(&point.operate_with_self(v.representative()));
+            assert_eq!(
+                Secp256k1::multiply_generator_and_add(u, v, &point),
+                expected
+            );
+        }
+
+        // u * G + v * (-(u / v) * G) is the point at infinity
+        let u = generator.random_scalar();
+        let v = generator.random_scalar();
+        let point = Secp256k1::generator().operate_with_self((-(&u / &v)).representative());
+        assert!(Secp256k1::multiply_generator_and_add(&u, &v, &point).is_neutral_element());
+    }
 }
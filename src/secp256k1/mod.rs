@@ -1,2 +1,3 @@
+pub mod constant_time;
 pub mod curve;
 pub mod fields;
@@ -0,0 +1,229 @@
+//! Encoding of non negative integers as ASN.1 DER `INTEGER`s, as used by ECDSA signatures.
+//!
+//! The content octets are the minimal two's complement big endian representation of the
+//! integer. For a non negative integer this means no leading zero bytes, except for a single
+//! one when the most significant bit of the next byte is set, and a single zero byte for zero.
+
+use lambdaworks_math::unsigned_integer::element::U256;
+
+use crate::byte_array::ByteArrayOfLength32;
+
+use super::{CanParse, CanSerialize, DERError, ParserError, U256BigEndianSerializer};
+
+/// Length and content octets of a DER integer. The `0x02` tag is left to the caller.
+pub(crate) struct U256DERSerializer;
+
+impl CanSerialize<U256> for U256DERSerializer {
+    type Output = Vec<u8>;
+
+    fn serialize(element: &U256) -> Self::Output {
+        let mut content: Vec<u8> = U256BigEndianSerializer::serialize(element)
+            .into_iter()
+            .skip_while(|&byte| byte == 0)
+            .collect();
+
+        // Prepending a zero byte covers both the integer zero and a leading byte that would
+        // otherwise be read as the sign bit.
+        if !matches!(content.first(), Some(&byte) if byte & 0x80 == 0) {
+            content.insert(0, 0x00);
+        }
+
+        let mut result = serialize_length(content.len());
+        result.extend(content);
+        result
+    }
+}
+
+impl CanParse<U256> for U256DERSerializer {
+    /// Parses the length and content octets of a DER integer, enforcing the BIP 66 rules:
+    /// non empty, non negative and without superfluous leading zeros.
+    fn parse(bytes: &[u8]) -> Result<(U256, usize), ParserError> {
+        let length = *bytes.first().ok_or(DERError::UnexpectedEnd)? as usize;
+        if length == 0 {
+            return Err(DERError::ZeroLengthInteger.into());
+        }
+        if length & 0x80 != 0 {
+            return Err(DERError::LengthMismatch.into());
+        }
+        let content = bytes.get(1..(1 + length)).ok_or(DERError::LengthMismatch)?;
+        if content[0] & 0x80 != 0 {
+            return Err(DERError::NegativeInteger.into());
+        }
+        if length > 1 && content[0] == 0 && content[1] & 0x80 == 0 {
+            return Err(DERError::ExcessPadding.into());
+        }
+        Ok((u256_from_be_bytes(content)?, 1 + length))
+    }
+}
+
+/// DER length octets: the short form below 128 and the long form otherwise.
+pub(crate) fn serialize_length(length: usize) -> Vec<u8> {
+    if length < 0x80 {
+        return vec![length as u8];
+    }
+    let length_bytes: Vec<u8> = length
+        .to_be_bytes()
+        .into_iter()
+        .skip_while(|&byte| byte == 0)
+        .collect();
+    let mut result = vec![0x80 | length_bytes.len() as u8];
+    result.extend(length_bytes);
+    result
+}
+
+/// Interprets `bytes` as an unsigned big endian integer, ignoring any leading zeros.
+pub(crate) fn u256_from_be_bytes(bytes: &[u8]) -> Result<U256, DERError> {
+    let significant_bytes: Vec<u8> = bytes
+        .iter()
+        .copied()
+        .skip_while(|&byte| byte == 0)
+        .collect();
+    if significant_bytes.len() > 32 {
+        return Err(DERError::IntegerOverflow);
+    }
+    let mut padded = [0u8; 32];
+    padded[(32 - significant_bytes.len())..].copy_from_slice(&significant_bytes);
+    Ok(ByteArrayOfLength32::new(padded).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use lambdaworks_math::{
+        field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
+    };
+    use rand::Rng;
+
+    use crate::{
+        secp256k1::fields::ScalarFieldModulus,
+        serializer::{
+            der::serialize_length, CanParse, CanSerialize, DERError, ParserError, U256DERSerializer,
+        },
+    };
+
+    fn edge_values() -> Vec<U256> {
+        vec![
+            U256::from_u64(0),
+            U256::from_u64(1),
+            U256::from_u64(0x7f),
+            U256::from_u64(0x80),
+            U256::from_u64(0xff),
+            U256::from_u64(0x100),
+            U256::from_u64(0x7fff),
+            U256::from_u64(0x8000),
+            U256::from_hex_unchecked(
+                "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            ),
+            U256::from_hex_unchecked(
+                "8000000000000000000000000000000000000000000000000000000000000000",
+            ),
+            U256::from_hex_unchecked(
+                "0080000000000000000000000000000000000000000000000000000000000000",
+            ),
+            ScalarFieldModulus::MODULUS - U256::from_u64(1),
+            U256::from_hex_unchecked(
+                "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_serialize_u256_element_der_format() {
+        let element =
+            U256::from_hex_unchecked("05c63fdc786d6a6b904080b58f72edb08da1cf2d309539336a");
+        let expected_bytes = [
+            25, 5, 198, 63, 220, 120, 109, 106, 107, 144, 64, 128, 181, 143, 114, 237, 176, 141,
+            161, 207, 45, 48, 149, 57, 51, 106,
+        ];
+        let serialized_element = U256DERSerializer::serialize(&element);
+        assert_eq!(serialized_element, expected_bytes);
+    }
+
+    #[test]
+    fn test_serialize_u256_der_leading_0x80_is_padded() {
+        let element = U256::from_u64(0x80);
+        let serialized_element = U256DERSerializer::serialize(&element);
+        assert_eq!(serialized_element, [2, 0, 0x80]);
+    }
+
+    #[test]
+    fn test_serialize_u256_der_zero() {
+        let serialized_element = U256DERSerializer::serialize(&U256::from_u64(0));
+        assert_eq!(serialized_element, [1, 0]);
+    }
+
+    #[test]
+    fn test_serialize_u256_der_max_scalar() {
+        let element = ScalarFieldModulus::MODULUS - U256::from_u64(1);
+        let serialized_element = U256DERSerializer::serialize(&element);
+        assert_eq!(serialized_element.len(), 1 + 33);
+        assert_eq!(serialized_element[..3], [33, 0, 0xff]);
+    }
+
+    #[test]
+    fn test_parse_u256_element_der_format() {
+        let bytes = [
+            25, 5, 198, 63, 220, 120, 109, 106, 107, 144, 64, 128, 181, 143, 114, 237, 176, 141,
+            161, 207, 45, 48, 149, 57, 51, 106, 2,
+        ];
+        let expected_element =
+            U256::from_hex_unchecked("05c63fdc786d6a6b904080b58f72edb08da1cf2d309539336a");
+        let (element, length) = U256DERSerializer::parse(&bytes).unwrap();
+        assert_eq!(element, expected_element);
+        assert_eq!(length, 26);
+    }
+
+    #[test]
+    fn test_parse_u256_der_rejects_negative() {
+        let bytes = [1, 0x80];
+        let error = U256DERSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::NegativeInteger));
+    }
+
+    #[test]
+    fn test_parse_u256_der_rejects_excess_padding() {
+        let bytes = [2, 0, 0x7f];
+        let error = U256DERSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::ExcessPadding));
+    }
+
+    #[test]
+    fn test_parse_u256_der_rejects_empty() {
+        let bytes = [0];
+        let error = U256DERSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::ZeroLengthInteger));
+    }
+
+    #[test]
+    fn test_u256_der_round_trip_edge_values() {
+        for element in edge_values() {
+            let bytes = U256DERSerializer::serialize(&element);
+            let (parsed_element, length) = U256DERSerializer::parse(&bytes).unwrap();
+            assert_eq!(parsed_element, element);
+            assert_eq!(length, bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_u256_der_round_trip_random_values() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            // Random number of leading zero bits to cover every content length
+            let shift = rng.gen_range(0..256);
+            let element = U256::from_limbs([rng.gen(), rng.gen(), rng.gen(), rng.gen()]) >> shift;
+            let bytes = U256DERSerializer::serialize(&element);
+            assert_eq!(bytes[0] as usize, bytes.len() - 1);
+            assert_eq!(bytes[1] & 0x80, 0);
+            let (parsed_element, length) = U256DERSerializer::parse(&bytes).unwrap();
+            assert_eq!(parsed_element, element);
+            assert_eq!(length, bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_serialize_length() {
+        assert_eq!(serialize_length(0), [0]);
+        assert_eq!(serialize_length(0x7f), [0x7f]);
+        assert_eq!(serialize_length(0x80), [0x81, 0x80]);
+        assert_eq!(serialize_length(0x1234), [0x82, 0x12, 0x34]);
+    }
+}
@@ -1,3 +1,4 @@
+mod der;
 mod public_key;
 mod script;
 mod signature;
@@ -5,11 +6,13 @@ mod transaction;
 mod u256;
 mod u64;
 
+pub(crate) use self::der::U256DERSerializer;
 pub(crate) use self::script::ScriptSerializer;
+pub(crate) use self::signature::{ECDSASignatureLaxSerializer, ECDSASignatureSerializer};
 pub(crate) use self::u256::U256BigEndianSerializer;
-pub(crate) use self::u256::U256DERSerializer;
 pub(crate) use self::u64::VarIntSerializer;
 
+pub(crate) use self::public_key::FeltSerializer;
 pub(crate) use self::public_key::PublicKeyCompressedSerializer;
 pub(crate) use self::public_key::PublicKeyUncompressedSerializer;
 
@@ -19,6 +22,44 @@ pub enum SerializerError {}
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParserError {
     ParseError,
+    DERError(DERError),
+}
+
+/// Reasons for rejecting a DER encoded signature. The first group of variants are encodings
+/// that cannot be read at all. The second group are the BIP 66 strictness rules, which historic
+/// blocks predating the soft fork violate and lax parsing tolerates.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DERError {
+    UnexpectedEnd,
+    InvalidSequenceTag,
+    InvalidIntegerTag,
+    IntegerOverflow,
+
+    InvalidTotalLength,
+    LengthMismatch,
+    ZeroLengthInteger,
+    NegativeInteger,
+    ExcessPadding,
+}
+
+impl DERError {
+    /// Whether the error is a violation of BIP 66 that lax parsing accepts.
+    pub fn is_bip66_violation(&self) -> bool {
+        matches!(
+            self,
+            DERError::InvalidTotalLength
+                | DERError::LengthMismatch
+                | DERError::ZeroLengthInteger
+                | DERError::NegativeInteger
+                | DERError::ExcessPadding
+        )
+    }
+}
+
+impl From<DERError> for ParserError {
+    fn from(error: DERError) -> Self {
+        ParserError::DERError(error)
+    }
 }
 
 pub(crate) fn read_bytes<const N: usize>(bytes: &[u8]) -> Result<[u8; N], ParserError> {
@@ -1,5 +1,5 @@
 use lambdaworks_math::{
-    elliptic_curve::{short_weierstrass::traits::IsShortWeierstrass, traits::IsEllipticCurve},
+    elliptic_curve::traits::FromAffine,
     field::{
         element::FieldElement,
         fields::montgomery_backed_prime_fields::{IsModulus, MontgomeryBackendPrimeField},
@@ -9,7 +9,10 @@ use lambdaworks_math::{
 
 use crate::{
     public_key::PublicKey,
-    secp256k1::{curve::Secp256k1, fields::BaseFelt},
+    secp256k1::{
+        curve::{Point, Secp256k1},
+        fields::BaseFelt,
+    },
 };
 
 use super::{read_bytes, CanParse, CanSerialize, ParserError, U256BigEndianSerializer};
@@ -73,21 +76,22 @@ impl CanParse<PublicKey> for PublicKeyCompressedSerializer {
         let flag = read_bytes::<1>(bytes)?[0];
         let serialized_x = read_bytes::<32>(&bytes[1..])?;
         let x = BaseFelt::new(U256BigEndianSerializer::parse(&serialized_x)?.0);
-        if let Some((y1, y2)) = (x.pow(3u64) + Secp256k1::a() * &x + Secp256k1::b()).sqrt() {
-            let (y1, y2) = if y1.representative().limbs[3] & 1 == 0 {
-                (y1, y2)
-            } else {
-                (y2, y1)
-            };
-            let point = if flag == 2 {
-                Secp256k1::create_point_from_affine(x, y1).map_err(|_| ParserError::ParseError)?
-            } else {
-                Secp256k1::create_point_from_affine(x, y2).map_err(|_| ParserError::ParseError)?
-            };
-            Ok((PublicKey::new(point), 1 + 32))
-        } else {
-            Err(ParserError::ParseError)
+        let point = Secp256k1::lift_x(&x, flag != 2).ok_or(ParserError::ParseError)?;
+        Ok((PublicKey::new(point), 1 + 32))
+    }
+}
+
+impl CanParse<PublicKey> for PublicKeyUncompressedSerializer {
+    fn parse(bytes: &[u8]) -> Result<(PublicKey, usize), ParserError> {
+        if read_bytes::<1>(bytes)?[0] != 4 {
+            return Err(ParserError::ParseError);
         }
+        let serialized_x = read_bytes::<32>(&bytes[1..])?;
+        let serialized_y = read_bytes::<32>(&bytes[(1 + 32)..])?;
+        let x = BaseFelt::new(U256BigEndianSerializer::parse(&serialized_x)?.0);
+        let y = BaseFelt::new(U256BigEndianSerializer::parse(&serialized_y)?.0);
+        let point = Point::from_affine(x, y).map_err(|_| ParserError::ParseError)?;
+        Ok((PublicKey::new(point), 1 + 32 + 32))
     }
 }
 
@@ -280,4 +284,20 @@ mod tests {
         let public_key = PublicKeyCompressedSerializer::parse(&bytes).unwrap().0;
         assert_eq!(public_key, expected_public_key);
     }
+
+    #[test]
+    fn test_parse_public_key_uncompressed_sec() {
+        let public_key = PublicKey::from_u256(U256::from_u64(5000u64));
+        let mut bytes = PublicKeyUncompressedSerializer::serialize(&public_key);
+        let (parsed_public_key, length) = PublicKeyUncompressedSerializer::parse(&bytes).unwrap();
+        assert_eq!(parsed_public_key, public_key);
+        assert_eq!(length, 65);
+
+        assert!(PublicKeyUncompressedSerializer::parse(&bytes[..64]).is_err());
+        bytes[64] ^= 1;
+        assert!(PublicKeyUncompressedSerializer::parse(&bytes).is_err());
+        bytes[64] ^= 1;
+        bytes[0] = 2;
+        assert!(PublicKeyUncompressedSerializer::parse(&bytes).is_err());
+    }
 }
@@ -1,4 +1,7 @@
-use crate::transaction::{Command, Script};
+use crate::{
+    opcode::Opcode,
+    transaction::{Command, Script},
+};
 
 use super::{read_bytes, CanParse, CanSerialize, ParserError, VarIntSerializer};
 
@@ -7,102 +10,113 @@ pub(crate) struct ScriptSerializer;
 impl ScriptSerializer {
     fn serialize_command(command: &Command) -> Vec<u8> {
         match command {
-            Command::Operation(value) => vec![*value],
+            Command::Operation(opcode) => vec![u8::from(*opcode)],
             Command::Element(element_bytes) => {
-                let length = element_bytes.len();
-                if length <= 75 {
-                    let mut result = vec![length as u8];
-                    result.extend_from_slice(element_bytes);
-                    result
-                } else if 75 < length && length < 0x100 {
-                    let mut result = vec![76, length as u8];
-                    result.extend_from_slice(element_bytes);
-                    result
-                } else if (0x100..0x10000).contains(&length) {
-                    let length_as_bytes = &length.to_le_bytes()[..2];
-                    let mut result = vec![77];
-                    result.extend_from_slice(length_as_bytes);
-                    result.extend_from_slice(element_bytes);
-                    result
-                } else {
-                    // Code unreachable given Script's invariants
-                    vec![]
-                }
+                Self::serialize_push(Opcode::minimal_push(element_bytes), element_bytes)
             }
+            Command::Push(opcode, element_bytes) => Self::serialize_push(*opcode, element_bytes),
         }
     }
-}
 
-impl CanSerialize<Script> for ScriptSerializer {
-    type Output = Vec<u8>;
+    /// `opcode` followed by the length of the element for `OP_PUSHDATAn` and by the element,
+    /// unless `opcode` is one of `OP_1NEGATE` and `OP_1` to `OP_16`.
+    fn serialize_push(opcode: Opcode, element_bytes: &[u8]) -> Vec<u8> {
+        let length = element_bytes.len();
+        let mut result = vec![u8::from(opcode)];
+        match opcode {
+            Opcode::OP_PUSHDATA1 => result.push(length as u8),
+            Opcode::OP_PUSHDATA2 => result.extend_from_slice(&(length as u16).to_le_bytes()),
+            Opcode::OP_PUSHDATA4 => result.extend_from_slice(&(length as u32).to_le_bytes()),
+            _ => {}
+        }
+        if opcode <= Opcode::OP_PUSHDATA4 {
+            result.extend_from_slice(element_bytes);
+        }
+        result
+    }
 
-    fn serialize(script: &Script) -> Self::Output {
-        let serialized_script: Vec<u8> = script
+    /// Serializes the commands of the script without the length prefix, which is what gets
+    /// hashed into P2SH and P2WSH outputs.
+    pub(crate) fn serialize_commands(script: &Script) -> Vec<u8> {
+        script
             .commands()
             .iter()
             .flat_map(Self::serialize_command)
-            .collect();
-        let mut result = VarIntSerializer::serialize(&(serialized_script.len() as u64));
-        result.extend_from_slice(&serialized_script);
-        result
+            .collect()
     }
-}
 
-impl CanParse<Script> for ScriptSerializer {
-    fn parse(bytecode: &[u8]) -> Result<(Script, usize), super::ParserError> {
-        let (length, length_prefix) = VarIntSerializer::parse(bytecode)?;
-        let bytecode = &bytecode[length_prefix..];
+    /// Parses commands filling the whole of `bytecode`, which has no length prefix. Pushes that
+    /// don't use the shortest encoding are kept as `Command::Push`.
+    pub(crate) fn parse_commands(bytecode: &[u8]) -> Result<Script, ParserError> {
         let mut count = 0;
         let mut commands = Vec::new();
-        while count < length as usize {
-            let command = match bytecode.get(count) {
-                Some(&value) if value <= 75 => {
+        while count < bytecode.len() {
+            let opcode = Opcode::from(bytecode[count]);
+            count += 1;
+            let element_length = match opcode {
+                Opcode::OP_PUSHDATA1 => {
+                    let length_bytes = read_bytes::<1>(&bytecode[count..])?;
                     count += 1;
-                    let element_bytes = bytecode
-                        .get(count..(count + value as usize))
-                        .ok_or(ParserError::ParseError)?;
-                    count += value as usize;
-                    Command::Element(element_bytes.to_vec())
+                    length_bytes[0] as usize
                 }
-                Some(&76) => {
-                    count += 1;
-                    let element_length =
-                        *bytecode.get(count).ok_or(ParserError::ParseError)? as usize;
-                    count += 1;
-                    let element_bytes = bytecode
-                        .get(count..(count + element_length))
-                        .ok_or(ParserError::ParseError)?;
-                    count += element_length;
-                    Command::Element(element_bytes.to_vec())
-                }
-                Some(&77) => {
-                    count += 1;
-                    let element_length_bytes = read_bytes::<2>(&bytecode[count..])?;
-                    let element_length = u16::from_le_bytes(element_length_bytes) as usize;
+                Opcode::OP_PUSHDATA2 => {
+                    let length_bytes = read_bytes::<2>(&bytecode[count..])?;
                     count += 2;
-                    let element_bytes = bytecode
-                        .get(count..(count + element_length))
-                        .ok_or(ParserError::ParseError)?;
-                    count += element_length;
-                    Command::Element(element_bytes.to_vec())
+                    u16::from_le_bytes(length_bytes) as usize
                 }
-                Some(&value) => {
-                    count += 1;
-                    Command::Operation(value)
+                Opcode::OP_PUSHDATA4 => {
+                    let length_bytes = read_bytes::<4>(&bytecode[count..])?;
+                    count += 4;
+                    u32::from_le_bytes(length_bytes) as usize
+                }
+                opcode if opcode < Opcode::OP_PUSHDATA1 => u8::from(opcode) as usize,
+                opcode => {
+                    commands.push(Command::Operation(opcode));
+                    continue;
                 }
-                None => return Err(ParserError::ParseError),
             };
-            commands.push(command);
+            let element_bytes = bytecode[count..]
+                .get(..element_length)
+                .ok_or(ParserError::ParseError)?
+                .to_vec();
+            count += element_length;
+            if Opcode::minimal_push(&element_bytes) == opcode {
+                commands.push(Command::Element(element_bytes));
+            } else {
+                commands.push(Command::Push(opcode, element_bytes));
+            }
         }
 
-        let script = Script::new(commands).map_err(|_| ParserError::ParseError)?;
-        Ok((script, length_prefix + count))
+        Script::new(commands).map_err(|_| ParserError::ParseError)
+    }
+}
+
+impl CanSerialize<Script> for ScriptSerializer {
+    type Output = Vec<u8>;
+
+    fn serialize(script: &Script) -> Self::Output {
+        let serialized_script = Self::serialize_commands(script);
+        let mut result = VarIntSerializer::serialize(&(serialized_script.len() as u64));
+        result.extend_from_slice(&serialized_script);
+        result
+    }
+}
+
+impl CanParse<Script> for ScriptSerializer {
+    fn parse(bytecode: &[u8]) -> Result<(Script, usize), super::ParserError> {
+        let (length, length_prefix) = VarIntSerializer::parse(bytecode)?;
+        let end = length_prefix + length as usize;
+        let script_bytes = bytecode
+            .get(length_prefix..end)
+            .ok_or(ParserError::ParseError)?;
+        Ok((Self::parse_commands(script_bytes)?, end))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
+        opcode::Opcode,
         serializer::{script::ScriptSerializer, CanParse, CanSerialize},
         transaction::{Command, Script},
     };
@@ -116,7 +130,7 @@ mod tests {
                 109, 149, 35, 28, 216, 144, 38, 226, 134, 223, 59, 106, 228, 168, 148, 163, 55,
                 142, 57, 62, 147, 160, 244, 91, 102, 99, 41, 160, 174, 52,
             ]),
-            Command::Operation(0xac),
+            Command::Operation(Opcode::OP_CHECKSIG),
         ])
         .unwrap();
         let expected_bytes = [
@@ -199,14 +213,14 @@ mod tests {
                 121, 12, 120, 44, 118, 33, 86, 96, 221, 48, 151, 145, 208, 107, 208, 175, 63, 152,
                 205, 164, 188, 70, 41, 177,
             ]),
-            Command::Operation(0x6e),
-            Command::Operation(0x87),
-            Command::Operation(0x91),
-            Command::Operation(0x69),
-            Command::Operation(0xa7),
-            Command::Operation(0x7c),
-            Command::Operation(0xa7),
-            Command::Operation(0x87),
+            Command::Operation(Opcode::OP_2DUP),
+            Command::Operation(Opcode::OP_EQUAL),
+            Command::Operation(Opcode::OP_NOT),
+            Command::Operation(Opcode::OP_VERIFY),
+            Command::Operation(Opcode::OP_SHA1),
+            Command::Operation(Opcode::OP_SWAP),
+            Command::Operation(Opcode::OP_SHA1),
+            Command::Operation(Opcode::OP_EQUAL),
         ])
         .unwrap();
         let expected_bytes = [
@@ -264,7 +278,7 @@ mod tests {
                 109, 149, 35, 28, 216, 144, 38, 226, 134, 223, 59, 106, 228, 168, 148, 163, 55,
                 142, 57, 62, 147, 160, 244, 91, 102, 99, 41, 160, 174, 52,
             ]),
-            Command::Operation(0xac),
+            Command::Operation(Opcode::OP_CHECKSIG),
         ])
         .unwrap();
         let (script, length) = ScriptSerializer::parse(&bytes).unwrap();
@@ -379,18 +393,65 @@ mod tests {
                 121, 12, 120, 44, 118, 33, 86, 96, 221, 48, 151, 145, 208, 107, 208, 175, 63, 152,
                 205, 164, 188, 70, 41, 177,
             ]),
-            Command::Operation(0x6e),
-            Command::Operation(0x87),
-            Command::Operation(0x91),
-            Command::Operation(0x69),
-            Command::Operation(0xa7),
-            Command::Operation(0x7c),
-            Command::Operation(0xa7),
-            Command::Operation(0x87),
+            Command::Operation(Opcode::OP_2DUP),
+            Command::Operation(Opcode::OP_EQUAL),
+            Command::Operation(Opcode::OP_NOT),
+            Command::Operation(Opcode::OP_VERIFY),
+            Command::Operation(Opcode::OP_SHA1),
+            Command::Operation(Opcode::OP_SWAP),
+            Command::Operation(Opcode::OP_SHA1),
+            Command::Operation(Opcode::OP_EQUAL),
         ])
         .unwrap();
         let (script, length) = ScriptSerializer::parse(&bytes).unwrap();
         assert_eq!(script, expected_script);
         assert_eq!(length, 657)
     }
+
+    #[test]
+    fn test_serialize_minimal_pushes() {
+        let script = Script::new(vec![
+            Command::Element(vec![]),
+            Command::Element(vec![0]),
+            Command::Element(vec![1]),
+            Command::Element(vec![16]),
+            Command::Element(vec![17]),
+            Command::Element(vec![0x81]),
+            Command::Element(vec![0xff; 76]),
+        ])
+        .unwrap();
+        let mut expected_bytes = vec![0, 1, 0, 0x51, 0x60, 1, 17, 0x4f, 0x4c, 76];
+        expected_bytes.extend_from_slice(&[0xff; 76]);
+        assert_eq!(
+            ScriptSerializer::serialize_commands(&script),
+            expected_bytes
+        );
+
+        let script = Script::new(vec![Command::Element(vec![7; 0x10000])]).unwrap();
+        let bytes = ScriptSerializer::serialize(&script);
+        assert_eq!(bytes[..8], [0xfe, 5, 0, 1, 0, 0x4e, 0, 0]);
+        assert_eq!(bytes[8..10], [1, 0]);
+        let (parsed_script, length) = ScriptSerializer::parse(&bytes).unwrap();
+        assert_eq!(parsed_script, script);
+        assert_eq!(length, bytes.len());
+    }
+
+    #[test]
+    fn test_parse_non_minimal_pushes() {
+        // A direct push of 5 instead of OP_5, and 2 bytes pushed with OP_PUSHDATA2
+        let bytes = [8, 1, 5, 0x4d, 2, 0, 0xab, 0xcd, 0x55];
+        let expected_script = Script::new(vec![
+            Command::Push(Opcode::OP_PUSHBYTES_1, vec![5]),
+            Command::Push(Opcode::OP_PUSHDATA2, vec![0xab, 0xcd]),
+            Command::Operation(Opcode::OP_5),
+        ])
+        .unwrap();
+        let (script, length) = ScriptSerializer::parse(&bytes).unwrap();
+        assert_eq!(script, expected_script);
+        assert_eq!(length, 9);
+        assert_eq!(ScriptSerializer::serialize(&script), bytes);
+
+        assert!(ScriptSerializer::parse_commands(&[0x4e, 1, 0, 0]).is_err());
+        assert!(ScriptSerializer::parse_commands(&[0x4e, 2, 0, 0, 0, 1]).is_err());
+    }
 }
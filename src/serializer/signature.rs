@@ -1,19 +1,40 @@
-use crate::signature::ECDSASignature;
+use lambdaworks_math::{
+    field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
+};
 
-use super::{CanSerialize, U256DERSerializer};
+use crate::{
+    secp256k1::fields::{ScalarFelt, ScalarFieldModulus},
+    signature::ECDSASignature,
+};
 
+use super::{
+    der::{serialize_length, u256_from_be_bytes},
+    CanParse, CanSerialize, DERError, ParserError, U256DERSerializer,
+};
+
+/// DER encoding of ECDSA signatures. Parsing enforces the strict rules of BIP 66.
 pub(crate) struct ECDSASignatureSerializer;
 
+/// Parser for the loosely DER encoded signatures found in blocks prior to BIP 66. It mirrors
+/// the lax parser of Bitcoin Core: lengths may use the long form, integers may be padded or
+/// have their sign bit set, and the sequence length is not checked against its contents.
+pub(crate) struct ECDSASignatureLaxSerializer;
+
+/// The shortest and longest strict DER signatures, excluding the sighash byte.
+const MIN_SIGNATURE_LENGTH: usize = 8;
+const MAX_SIGNATURE_LENGTH: usize = 72;
+
 impl CanSerialize<ECDSASignature> for ECDSASignatureSerializer {
     type Output = Vec<u8>;
 
     fn serialize(signature: &ECDSASignature) -> Self::Output {
         let serialized_r = U256DERSerializer::serialize(&signature.r.representative());
         let serialized_s = U256DERSerializer::serialize(&signature.s.representative());
-        let signature_length = 2 + serialized_r.len() + serialized_s.len();
-        let mut result = Vec::with_capacity(signature_length);
+        let sequence_length = 1 + serialized_r.len() + 1 + serialized_s.len();
+        let serialized_length = serialize_length(sequence_length);
+        let mut result = Vec::with_capacity(1 + serialized_length.len() + sequence_length);
         result.push(0x30);
-        result.push(signature_length as u8);
+        result.extend_from_slice(&serialized_length);
         result.push(2);
         result.extend_from_slice(&serialized_r);
         result.push(2);
@@ -22,11 +43,115 @@ impl CanSerialize<ECDSASignature> for ECDSASignatureSerializer {
     }
 }
 
+impl CanParse<ECDSASignature> for ECDSASignatureSerializer {
+    fn parse(bytes: &[u8]) -> Result<(ECDSASignature, usize), ParserError> {
+        match bytes.first() {
+            Some(0x30) => {}
+            Some(_) => return Err(DERError::InvalidSequenceTag.into()),
+            None => return Err(DERError::UnexpectedEnd.into()),
+        }
+        let sequence_length = *bytes.get(1).ok_or(DERError::UnexpectedEnd)? as usize;
+        let total_length = 2 + sequence_length;
+        if !(MIN_SIGNATURE_LENGTH..=MAX_SIGNATURE_LENGTH).contains(&total_length) {
+            return Err(DERError::InvalidTotalLength.into());
+        }
+        let content = bytes.get(2..total_length).ok_or(DERError::UnexpectedEnd)?;
+
+        let (r, r_length) = Self::parse_integer(content)?;
+        let (s, s_length) = Self::parse_integer(&content[r_length..])?;
+        if r_length + s_length != sequence_length {
+            return Err(DERError::LengthMismatch.into());
+        }
+
+        Ok((ECDSASignature::new(r, s), total_length))
+    }
+}
+
+impl ECDSASignatureSerializer {
+    fn parse_integer(bytes: &[u8]) -> Result<(ScalarFelt, usize), ParserError> {
+        match bytes.first() {
+            Some(0x02) => {}
+            Some(_) => return Err(DERError::InvalidIntegerTag.into()),
+            None => return Err(DERError::LengthMismatch.into()),
+        }
+        let (integer, length) = U256DERSerializer::parse(&bytes[1..])?;
+        Ok((scalar_from_u256(integer)?, 1 + length))
+    }
+}
+
+impl CanParse<ECDSASignature> for ECDSASignatureLaxSerializer {
+    fn parse(bytes: &[u8]) -> Result<(ECDSASignature, usize), ParserError> {
+        match bytes.first() {
+            Some(0x30) => {}
+            Some(_) => return Err(DERError::InvalidSequenceTag.into()),
+            None => return Err(DERError::UnexpectedEnd.into()),
+        }
+        // The sequence length is read only to know how many bytes to skip.
+        let (_, mut position) = Self::parse_length(bytes, 1)?;
+
+        let (r, r_end) = Self::parse_integer(bytes, position)?;
+        position = r_end;
+        let (s, s_end) = Self::parse_integer(bytes, position)?;
+
+        Ok((ECDSASignature::new(r, s), s_end))
+    }
+}
+
+impl ECDSASignatureLaxSerializer {
+    /// Reads a short or long form length starting at `position`. Returns the length and the
+    /// position right after it.
+    fn parse_length(bytes: &[u8], position: usize) -> Result<(usize, usize), DERError> {
+        let first = *bytes.get(position).ok_or(DERError::UnexpectedEnd)?;
+        if first & 0x80 == 0 {
+            return Ok((first as usize, position + 1));
+        }
+        let length_bytes = bytes
+            .get((position + 1)..(position + 1 + (first & 0x7f) as usize))
+            .ok_or(DERError::UnexpectedEnd)?;
+        let significant_bytes: Vec<u8> = length_bytes
+            .iter()
+            .copied()
+            .skip_while(|&byte| byte == 0)
+            .collect();
+        if significant_bytes.len() > std::mem::size_of::<usize>() {
+            return Err(DERError::UnexpectedEnd);
+        }
+        let length = significant_bytes
+            .iter()
+            .fold(0usize, |acc, &byte| (acc << 8) | byte as usize);
+        Ok((length, position + 1 + length_bytes.len()))
+    }
+
+    /// Reads an integer starting at `position`. Returns it and the position right after it.
+    fn parse_integer(bytes: &[u8], position: usize) -> Result<(ScalarFelt, usize), DERError> {
+        match bytes.get(position) {
+            Some(0x02) => {}
+            Some(_) => return Err(DERError::InvalidIntegerTag),
+            None => return Err(DERError::UnexpectedEnd),
+        }
+        let (length, start) = Self::parse_length(bytes, position + 1)?;
+        let end = start.checked_add(length).ok_or(DERError::UnexpectedEnd)?;
+        let content = bytes.get(start..end).ok_or(DERError::UnexpectedEnd)?;
+        Ok((scalar_from_u256(u256_from_be_bytes(content)?)?, end))
+    }
+}
+
+/// Scalars at or above the group order cannot appear in a valid signature.
+fn scalar_from_u256(integer: U256) -> Result<ScalarFelt, DERError> {
+    if integer >= ScalarFieldModulus::MODULUS {
+        return Err(DERError::IntegerOverflow);
+    }
+    Ok(ScalarFelt::new(integer))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         secp256k1::fields::ScalarFelt,
-        serializer::{signature::ECDSASignatureSerializer, CanSerialize},
+        serializer::{
+            signature::{ECDSASignatureLaxSerializer, ECDSASignatureSerializer},
+            CanParse, CanSerialize, DERError, ParserError,
+        },
         signature::ECDSASignature,
     };
 
@@ -48,4 +173,170 @@ mod tests {
         let serialized_signature = ECDSASignatureSerializer::serialize(&signature);
         assert_eq!(serialized_signature, expected_bytes);
     }
+
+    #[test]
+    fn test_parse_ecdsa_signature() {
+        // Signature of a scriptSig, followed by its SIGHASH_ALL byte
+        let bytes = [
+            48, 69, 2, 33, 0, 237, 129, 255, 25, 46, 117, 163, 253, 35, 4, 0, 77, 202, 219, 116,
+            111, 165, 226, 76, 80, 49, 204, 252, 242, 19, 32, 176, 39, 116, 87, 201, 143, 2, 32,
+            122, 152, 109, 149, 92, 110, 12, 179, 93, 68, 106, 137, 211, 245, 97, 0, 244, 215, 246,
+            120, 1, 195, 25, 103, 116, 58, 156, 142, 16, 97, 91, 237, 1,
+        ];
+        let expected_signature = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "7a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed",
+            ),
+        );
+
+        let (signature, length) = ECDSASignatureSerializer::parse(&bytes).unwrap();
+        assert_eq!(signature, expected_signature);
+        assert_eq!(length, 71);
+
+        let (signature, length) = ECDSASignatureLaxSerializer::parse(&bytes).unwrap();
+        assert_eq!(signature, expected_signature);
+        assert_eq!(length, 71);
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_round_trip() {
+        let signature = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "37206a0610995c58074999cb9767b87af4c4978db68c06e8e6e81d282047a7c6",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "8ca63759c1157ebeaec0d03cecca119fc9a75bf8e6d0fa65c841c8e2738cdaec",
+            ),
+        );
+        let bytes = ECDSASignatureSerializer::serialize(&signature);
+        let (parsed_signature, length) = ECDSASignatureSerializer::parse(&bytes).unwrap();
+        assert_eq!(parsed_signature, signature);
+        assert_eq!(length, bytes.len());
+    }
+
+    #[test]
+    fn test_ecdsa_signature_round_trip_edge_values() {
+        let values = [
+            "1",
+            "7f",
+            "80",
+            "ff",
+            "8000000000000000000000000000000000000000000000000000000000000000",
+            "0080000000000000000000000000000000000000000000000000000000000000",
+            "7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0",
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+        ];
+        for r in values.iter() {
+            for s in values.iter() {
+                let signature = ECDSASignature::new(
+                    ScalarFelt::from_hex_unchecked(r),
+                    ScalarFelt::from_hex_unchecked(s),
+                );
+                let bytes = ECDSASignatureSerializer::serialize(&signature);
+                assert_eq!(bytes[1] as usize, bytes.len() - 2);
+                let (parsed_signature, length) = ECDSASignatureSerializer::parse(&bytes).unwrap();
+                assert_eq!(parsed_signature, signature);
+                assert_eq!(length, bytes.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_negative_integer() {
+        // r = 0x81 encoded without the leading zero byte
+        let bytes = [48, 6, 2, 1, 129, 2, 1, 1];
+
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::NegativeInteger));
+
+        let (signature, length) = ECDSASignatureLaxSerializer::parse(&bytes).unwrap();
+        assert_eq!(
+            signature,
+            ECDSASignature::new(ScalarFelt::from(0x81), ScalarFelt::from(1))
+        );
+        assert_eq!(length, 8);
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_excess_padding() {
+        let bytes = [48, 7, 2, 2, 0, 1, 2, 1, 1];
+
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::ExcessPadding));
+
+        let (signature, _) = ECDSASignatureLaxSerializer::parse(&bytes).unwrap();
+        assert_eq!(
+            signature,
+            ECDSASignature::new(ScalarFelt::from(1), ScalarFelt::from(1))
+        );
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_long_form_length() {
+        let bytes = [48, 129, 6, 2, 1, 1, 2, 1, 1];
+
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert!(matches!(error, ParserError::DERError(e) if e.is_bip66_violation()));
+
+        let (signature, length) = ECDSASignatureLaxSerializer::parse(&bytes).unwrap();
+        assert_eq!(
+            signature,
+            ECDSASignature::new(ScalarFelt::from(1), ScalarFelt::from(1))
+        );
+        assert_eq!(length, 9);
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_length_mismatch() {
+        let bytes = [48, 7, 2, 1, 1, 2, 1, 1, 0];
+
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::LengthMismatch));
+
+        let (_, length) = ECDSASignatureLaxSerializer::parse(&bytes).unwrap();
+        assert_eq!(length, 8);
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_invalid_tags() {
+        let bytes = [49, 6, 2, 1, 1, 2, 1, 1];
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::InvalidSequenceTag));
+        let error = ECDSASignatureLaxSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::InvalidSequenceTag));
+
+        let bytes = [48, 6, 2, 1, 1, 3, 1, 1];
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::InvalidIntegerTag));
+        let error = ECDSASignatureLaxSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::InvalidIntegerTag));
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_truncated() {
+        let bytes = [48, 6, 2, 1, 1, 2, 1];
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::UnexpectedEnd));
+        let error = ECDSASignatureLaxSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_parse_ecdsa_signature_scalar_overflow() {
+        // r equal to the group order
+        let mut bytes = vec![48, 38, 2, 33, 0];
+        bytes.extend_from_slice(&[
+            255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 254, 186,
+            174, 220, 230, 175, 72, 160, 59, 191, 210, 94, 140, 208, 54, 65, 65,
+        ]);
+        bytes.extend_from_slice(&[2, 1, 1]);
+
+        let error = ECDSASignatureSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::IntegerOverflow));
+        let error = ECDSASignatureLaxSerializer::parse(&bytes).unwrap_err();
+        assert_eq!(error, ParserError::DERError(DERError::IntegerOverflow));
+    }
 }
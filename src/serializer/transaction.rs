@@ -109,6 +109,7 @@ impl CanParse<Transaction> for TransactionSerializer {
 #[cfg(test)]
 mod tests {
     use crate::{
+        opcode::Opcode,
         serializer::{CanParse, CanSerialize},
         transaction::{Command, Input, Output, Script, Transaction},
     };
@@ -216,7 +217,7 @@ mod tests {
             ],
             26,
             Script::new(vec![
-                Command::Operation(82),
+                Command::Operation(Opcode::OP_2),
                 Command::Element(vec![
                     2, 38, 38, 233, 85, 234, 110, 166, 217, 136, 80, 201, 148, 249, 16, 123, 3,
                     107, 19, 52, 241, 140, 168, 131, 11, 255, 241, 41, 93, 33, 207, 219, 112,
@@ -225,8 +226,8 @@ mod tests {
                     3, 178, 135, 234, 241, 34, 238, 166, 144, 48, 160, 233, 254, 237, 9, 107, 237,
                     128, 69, 200, 185, 139, 236, 69, 62, 31, 250, 199, 251, 219, 212, 187, 113,
                 ]),
-                Command::Operation(82),
-                Command::Operation(174),
+                Command::Operation(Opcode::OP_2),
+                Command::Operation(Opcode::OP_CHECKMULTISIG),
             ])
             .unwrap(),
             0xffffffff,
@@ -255,7 +256,7 @@ mod tests {
             ],
             26,
             Script::new(vec![
-                Command::Operation(82),
+                Command::Operation(Opcode::OP_2),
                 Command::Element(vec![
                     2, 38, 38, 233, 85, 234, 110, 166, 217, 136, 80, 201, 148, 249, 16, 123, 3,
                     107, 19, 52, 241, 140, 168, 131, 11, 255, 241, 41, 93, 33, 207, 219, 112,
@@ -264,8 +265,8 @@ mod tests {
                     3, 178, 135, 234, 241, 34, 238, 166, 144, 48, 160, 233, 254, 237, 9, 107, 237,
                     128, 69, 200, 185, 139, 236, 69, 62, 31, 250, 199, 251, 219, 212, 187, 113,
                 ]),
-                Command::Operation(82),
-                Command::Operation(174),
+                Command::Operation(Opcode::OP_2),
+                Command::Operation(Opcode::OP_CHECKMULTISIG),
             ])
             .unwrap(),
             0xffffffff,
@@ -284,14 +285,14 @@ mod tests {
         let expected_output = Output::new(
             40000000,
             Script::new(vec![
-                Command::Operation(118),
-                Command::Operation(169),
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
                 Command::Element(vec![
                     60, 130, 215, 223, 54, 78, 182, 199, 91, 232, 200, 13, 242, 179, 237, 168, 219,
                     87, 57, 112,
                 ]),
-                Command::Operation(136),
-                Command::Operation(172),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
             ])
             .unwrap(),
         );
@@ -309,14 +310,14 @@ mod tests {
         let output = Output::new(
             40000000,
             Script::new(vec![
-                Command::Operation(118),
-                Command::Operation(169),
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
                 Command::Element(vec![
                     60, 130, 215, 223, 54, 78, 182, 199, 91, 232, 200, 13, 242, 179, 237, 168, 219,
                     87, 57, 112,
                 ]),
-                Command::Operation(136),
-                Command::Operation(172),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
             ])
             .unwrap(),
         );
@@ -334,14 +335,14 @@ mod tests {
         let expected_output = Output::new(
             1000273,
             Script::new(vec![
-                Command::Operation(118),
-                Command::Operation(169),
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
                 Command::Element(vec![
                     171, 12, 11, 46, 152, 177, 171, 109, 191, 103, 212, 117, 11, 10, 86, 36, 73,
                     72, 168, 121,
                 ]),
-                Command::Operation(136),
-                Command::Operation(172),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
             ])
             .unwrap(),
         );
@@ -359,14 +360,14 @@ mod tests {
         let output = Output::new(
             1000273,
             Script::new(vec![
-                Command::Operation(118),
-                Command::Operation(169),
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
                 Command::Element(vec![
                     171, 12, 11, 46, 152, 177, 171, 109, 191, 103, 212, 117, 11, 10, 86, 36, 73,
                     72, 168, 121,
                 ]),
-                Command::Operation(136),
-                Command::Operation(172),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
             ])
             .unwrap(),
         );
@@ -505,28 +506,28 @@ mod tests {
         let output1 = Output::new(
             1000273,
             Script::new(vec![
-                Command::Operation(118),
-                Command::Operation(169),
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
                 Command::Element(vec![
                     171, 12, 11, 46, 152, 177, 171, 109, 191, 103, 212, 117, 11, 10, 86, 36, 73,
                     72, 168, 121,
                 ]),
-                Command::Operation(136),
-                Command::Operation(172),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
             ])
             .unwrap(),
         );
         let output2 = Output::new(
             40000000,
             Script::new(vec![
-                Command::Operation(118),
-                Command::Operation(169),
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
                 Command::Element(vec![
                     60, 130, 215, 223, 54, 78, 182, 199, 91, 232, 200, 13, 242, 179, 237, 168, 219,
                     87, 57, 112,
                 ]),
-                Command::Operation(136),
-                Command::Operation(172),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
             ])
             .unwrap(),
         );
@@ -2,23 +2,44 @@ use crate::{
     byte_array::ByteArrayOfLength32,
     random::IsRandomGenerator,
     secp256k1::{
-        curve::Secp256k1,
-        fields::{BaseFelt, ScalarFelt, ScalarFieldModulus},
+        constant_time,
+        curve::{Point, Secp256k1},
+        fields::{BaseFelt, BaseFieldModulus, ScalarFelt, ScalarFieldModulus},
+    },
+    serializer::{
+        CanParse, CanSerialize, ECDSASignatureLaxSerializer, ECDSASignatureSerializer, ParserError,
+        U256BigEndianSerializer,
     },
     PrivateKey, PublicKey,
 };
 use lambdaworks_math::{
-    cyclic_group::IsGroup,
-    elliptic_curve::{short_weierstrass::traits::IsShortWeierstrass, traits::IsEllipticCurve},
-    field::fields::montgomery_backed_prime_fields::IsModulus,
+    cyclic_group::IsGroup, elliptic_curve::short_weierstrass::traits::IsShortWeierstrass,
+    field::fields::montgomery_backed_prime_fields::IsModulus, unsigned_integer::element::U256,
 };
 
-pub(crate) type Message = ByteArrayOfLength32;
+/// The 32 byte digest that gets signed, usually the `hash256` of the data being authorized.
+pub type Message = ByteArrayOfLength32;
+
+pub type Signature = ECDSASignature;
+
+/// An ECDSA signature together with the recovery id that identifies which of the candidate
+/// public keys produced it. Bit 0 of the id is the parity of the y-coordinate of the nonce
+/// point R and bit 1 tells whether its x-coordinate was at least the group order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecoverableSignature {
+    pub(crate) signature: ECDSASignature,
+    pub(crate) recovery_id: u8,
+}
 
 pub(crate) struct EllipticCurveDigitalSignatureAlgorithm;
 
-#[derive(Debug, PartialEq, Eq)]
-pub(crate) struct ECDSASignature {
+/// (n - 1) / 2, where n is the order of `secp256k1`. Signatures with `s` above this value are
+/// non-standard under BIP 62 / BIP 146.
+const HALF_CURVE_ORDER: U256 =
+    U256::from_hex_unchecked("7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ECDSASignature {
     pub(crate) r: ScalarFelt,
     pub(crate) s: ScalarFelt,
 }
@@ -27,35 +48,163 @@ impl ECDSASignature {
     pub(crate) fn new(r: ScalarFelt, s: ScalarFelt) -> Self {
         Self { r, s }
     }
+
+    /// Builds a signature from the big endian encodings of `r` and `s`, which must both be in
+    /// `[1, n)`.
+    pub fn from_bytes(r: [u8; 32], s: [u8; 32]) -> Result<Self, ParserError> {
+        let r: U256 = ByteArrayOfLength32::new(r).into();
+        let s: U256 = ByteArrayOfLength32::new(s).into();
+        let zero = U256::from_u64(0);
+        if r == zero
+            || s == zero
+            || r >= ScalarFieldModulus::MODULUS
+            || s >= ScalarFieldModulus::MODULUS
+        {
+            return Err(ParserError::ParseError);
+        }
+        Ok(Self::new(ScalarFelt::new(r), ScalarFelt::new(s)))
+    }
+
+    /// Big endian encoding of `r`.
+    pub fn r(&self) -> [u8; 32] {
+        U256BigEndianSerializer::serialize(&self.r.representative())
+    }
+
+    /// Big endian encoding of `s`.
+    pub fn s(&self) -> [u8; 32] {
+        U256BigEndianSerializer::serialize(&self.s.representative())
+    }
+
+    /// DER encoding of the signature, as it appears in scriptSigs and witnesses (without the
+    /// trailing sighash byte).
+    pub fn to_der(&self) -> Vec<u8> {
+        ECDSASignatureSerializer::serialize(self)
+    }
+
+    /// Parses a DER encoded signature enforcing the BIP 66 rules. Returns the signature and
+    /// the number of bytes read, so that a trailing sighash byte can be read afterwards.
+    pub fn from_der(bytes: &[u8]) -> Result<(Self, usize), ParserError> {
+        ECDSASignatureSerializer::parse(bytes)
+    }
+
+    /// Parses a signature that may not conform to BIP 66, such as those in old blocks.
+    pub fn from_der_lax(bytes: &[u8]) -> Result<(Self, usize), ParserError> {
+        ECDSASignatureLaxSerializer::parse(bytes)
+    }
+
+    /// Whether `s` lies in the lower half of the scalar range, as Bitcoin policy requires.
+    pub fn has_low_s(&self) -> bool {
+        self.s.representative() <= HALF_CURVE_ORDER
+    }
+
+    /// Replaces `s` by `n - s` if needed so that the signature has a low `s`. Both values
+    /// produce a valid signature for the same message and key.
+    pub fn normalize_s(&mut self) {
+        if !self.has_low_s() {
+            self.s = -&self.s;
+        }
+    }
+}
+
+impl RecoverableSignature {
+    pub(crate) fn new(signature: ECDSASignature, recovery_id: u8) -> Self {
+        Self {
+            signature,
+            recovery_id,
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn recovery_id(&self) -> u8 {
+        self.recovery_id
+    }
+
+    /// The 65 byte compact encoding used by `signmessage`: a header byte `27 + recovery_id`,
+    /// plus 4 if the signer's public key is meant to be serialized compressed, followed by the
+    /// big endian encodings of `r` and `s`.
+    pub fn to_compact(&self, compressed: bool) -> [u8; 65] {
+        let mut result = [0u8; 65];
+        result[0] = 27 + self.recovery_id + if compressed { 4 } else { 0 };
+        result[1..33].copy_from_slice(&self.signature.r());
+        result[33..].copy_from_slice(&self.signature.s());
+        result
+    }
+
+    /// Parses the compact encoding. Returns the signature and the compression flag of the
+    /// header.
+    pub fn from_compact(bytes: &[u8; 65]) -> Result<(Self, bool), ParserError> {
+        let header = bytes[0];
+        if !(27..=34).contains(&header) {
+            return Err(ParserError::ParseError);
+        }
+        let compressed = header >= 31;
+        let recovery_id = (header - 27) & 3;
+
+        let r: U256 = ByteArrayOfLength32::new(bytes[1..33].try_into().unwrap()).into();
+        let s: U256 = ByteArrayOfLength32::new(bytes[33..].try_into().unwrap()).into();
+        if r >= ScalarFieldModulus::MODULUS || s >= ScalarFieldModulus::MODULUS {
+            return Err(ParserError::ParseError);
+        }
+        let signature = ECDSASignature::new(ScalarFelt::new(r), ScalarFelt::new(s));
+        Ok((Self::new(signature, recovery_id), compressed))
+    }
 }
 
 impl EllipticCurveDigitalSignatureAlgorithm {
-    fn sign(
+    pub(crate) fn sign(
         message: &Message,
-        private_key: PrivateKey,
+        private_key: &PrivateKey,
         random: &mut impl IsRandomGenerator<ScalarFelt>,
     ) -> ECDSASignature {
+        Self::sign_recoverable(message, private_key, random).signature
+    }
+
+    pub(crate) fn sign_recoverable(
+        message: &Message,
+        private_key: &PrivateKey,
+        random: &mut impl IsRandomGenerator<ScalarFelt>,
+    ) -> RecoverableSignature {
         let z = ScalarFelt::new(message.into());
-        let e = ScalarFelt::new(private_key.into());
+        let e = private_key.scalar();
 
         loop {
             let k = random.random_scalar();
-            if let Ok(k_inv) = k.inv() {
-                let point = Secp256k1::generator()
-                    .operate_with_self(k.representative())
-                    .to_affine();
-                let r = ScalarFelt::new(point.x().representative());
+            if k != ScalarFelt::zero() {
+                let k_inv = constant_time::invert(&k);
+                let point = constant_time::to_affine(&constant_time::multiply_generator(&k));
+                let x = point.x().representative();
+                let r = ScalarFelt::new(x);
                 if r != ScalarFelt::zero() {
-                    let s = (&z + &e * &r) * k_inv;
+                    let s = constant_time::mul(
+                        &constant_time::add(&z, &constant_time::mul(&e, &r)),
+                        &k_inv,
+                    );
                     if s != ScalarFelt::zero() {
-                        return ECDSASignature::new(r, s);
+                        let mut recovery_id = (point.y().representative().limbs[3] & 1) as u8;
+                        if x >= ScalarFieldModulus::MODULUS {
+                            recovery_id |= 2;
+                        }
+                        let mut signature = ECDSASignature::new(r, s);
+                        if !signature.has_low_s() {
+                            // Negating s corresponds to signing with -k, whose point is -R
+                            signature.normalize_s();
+                            recovery_id ^= 1;
+                        }
+                        return RecoverableSignature::new(signature, recovery_id);
                     }
                 }
             }
         }
     }
 
-    fn verify(message: &Message, signature: ECDSASignature, public_key: PublicKey) -> bool {
+    pub(crate) fn verify(
+        message: &Message,
+        signature: &ECDSASignature,
+        public_key: &PublicKey,
+    ) -> bool {
         if public_key.point.z() == &BaseFelt::zero() {
             return false;
         }
@@ -77,10 +226,7 @@ impl EllipticCurveDigitalSignatureAlgorithm {
                 let z = ScalarFelt::new(message.into());
                 let u = z * &s_inv;
                 let v = &signature.r * s_inv;
-                let point = Secp256k1::generator()
-                    .operate_with_self(u.representative())
-                    .operate_with(&point.operate_with_self(v.representative()))
-                    .to_affine();
+                let point = Secp256k1::multiply_generator_and_add(&u, &v, &point).to_affine();
                 let r = ScalarFelt::new(point.x().representative());
 
                 r == signature.r
@@ -91,6 +237,56 @@ impl EllipticCurveDigitalSignatureAlgorithm {
             false
         }
     }
+
+    /// Computes the public key Q such that `signature` is valid for `message` under Q, as
+    /// `r^-1 (s R - z G)`, where R is the nonce point identified by `r` and the recovery id.
+    pub(crate) fn recover(
+        message: &Message,
+        signature: &RecoverableSignature,
+    ) -> Option<PublicKey> {
+        let nonce_point = Self::nonce_point(signature)?;
+        let ECDSASignature { r, s } = &signature.signature;
+
+        let r_inv = r.inv().ok()?;
+        let z = ScalarFelt::new(message.into());
+        let u = -z * &r_inv;
+        let v = s * r_inv;
+        let point = Secp256k1::multiply_generator_and_add(&u, &v, &nonce_point);
+        if point.is_neutral_element() {
+            return None;
+        }
+        Some(PublicKey::new(point))
+    }
+
+    /// The nonce point R identified by `r` and the recovery id, if `r` and `s` are nonzero and
+    /// such a point exists.
+    pub(crate) fn nonce_point(signature: &RecoverableSignature) -> Option<Point> {
+        let RecoverableSignature {
+            signature: ECDSASignature { r, s },
+            recovery_id,
+        } = signature;
+        if *r == ScalarFelt::zero() || *s == ScalarFelt::zero() || *recovery_id > 3 {
+            return None;
+        }
+
+        let mut x = r.representative();
+        if recovery_id & 2 != 0 {
+            if x >= BaseFieldModulus::MODULUS - ScalarFieldModulus::MODULUS {
+                return None;
+            }
+            x = x + ScalarFieldModulus::MODULUS;
+        }
+        Secp256k1::lift_x(&BaseFelt::new(x), recovery_id & 1 == 1)
+    }
+
+    /// Like [`Self::verify`], but additionally rejects signatures with a high `s`.
+    pub(crate) fn verify_strict(
+        message: &Message,
+        signature: &ECDSASignature,
+        public_key: &PublicKey,
+    ) -> bool {
+        signature.has_low_s() && Self::verify(message, signature, public_key)
+    }
 }
 
 #[cfg(test)]
@@ -98,16 +294,19 @@ pub mod tests {
     use lambdaworks_math::{
         cyclic_group::IsGroup,
         elliptic_curve::traits::{FromAffine, IsEllipticCurve},
+        field::fields::montgomery_backed_prime_fields::IsModulus,
     };
 
     use crate::{
-        hash::hash256,
+        hash::{hash256, sha256},
         secp256k1::{
             curve::{Point, Secp256k1},
-            fields::{BaseFelt, ScalarFelt},
+            fields::{BaseFelt, ScalarFelt, ScalarFieldModulus},
         },
+        serializer::{CanSerialize, ParserError, U256BigEndianSerializer},
         signature::{
             ECDSASignature, EllipticCurveDigitalSignatureAlgorithm as ECDSA, Message, PublicKey,
+            RecoverableSignature,
         },
         PrivateKey,
     };
@@ -123,17 +322,17 @@ pub mod tests {
 
     #[test]
     fn test_signature_1() {
-        let private_key = PrivateKey::new(hash256("my secret".as_bytes()));
+        let private_key = PrivateKey::new(hash256("my secret".as_bytes())).unwrap();
         let z = Message::new(hash256("my message".as_bytes()));
 
-        let signature = ECDSA::sign(&z, private_key, &mut TestRandomScalarGenerator);
+        let signature = ECDSA::sign(&z, &private_key, &mut TestRandomScalarGenerator);
 
         let signature_expected = ECDSASignature::new(
             ScalarFelt::from_hex_unchecked(
                 "2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22",
             ),
             ScalarFelt::from_hex_unchecked(
-                "bb14e602ef9e3f872e25fad328466b34e6734b7a0fcd58b1eb635447ffae8cb9",
+                "44eb19fd1061c078d1da052cd7b994c9d43b916c9f7b4789d46f0a44d087b488",
             ),
         );
 
@@ -145,10 +344,11 @@ pub mod tests {
         let private_key = PrivateKey::new([
             0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
             0, 48, 57,
-        ]);
+        ])
+        .unwrap();
         let z = Message::new(hash256("Programming Bitcoin!".as_bytes()));
 
-        let signature = ECDSA::sign(&z, private_key, &mut TestRandomScalarGenerator);
+        let signature = ECDSA::sign(&z, &private_key, &mut TestRandomScalarGenerator);
 
         let signature_expected = ECDSASignature::new(
             ScalarFelt::from_hex_unchecked(
@@ -188,7 +388,7 @@ pub mod tests {
             ),
         );
 
-        assert!(ECDSA::verify(&z, signature, public_key));
+        assert!(ECDSA::verify(&z, &signature, &public_key));
     }
 
     #[test]
@@ -219,7 +419,7 @@ pub mod tests {
             ),
         );
 
-        assert!(ECDSA::verify(&z, signature, public_key));
+        assert!(ECDSA::verify(&z, &signature, &public_key));
     }
 
     #[test]
@@ -250,7 +450,7 @@ pub mod tests {
             ),
         );
 
-        assert!(ECDSA::verify(&z, signature, public_key));
+        assert!(ECDSA::verify(&z, &signature, &public_key));
     }
 
     #[test]
@@ -282,6 +482,225 @@ pub mod tests {
         // Add noise to public key to make it invalid
         public_key = PublicKey::new(public_key.point().operate_with(&Secp256k1::generator()));
 
-        assert!(!ECDSA::verify(&z, signature, public_key));
+        assert!(!ECDSA::verify(&z, &signature, &public_key));
+    }
+
+    #[test]
+    fn test_sign_and_verify_with_public_api() {
+        let private_key = PrivateKey::new(hash256("my secret".as_bytes())).unwrap();
+        let z = Message::new(hash256("my message".as_bytes()));
+
+        let signature = private_key.sign(&z);
+        let public_key = PublicKey::from_private_key(private_key);
+        assert!(public_key.verify(&z, &signature));
+
+        let other_z = Message::new(hash256("other message".as_bytes()));
+        assert!(!public_key.verify(&other_z, &signature));
+    }
+
+    #[test]
+    fn test_signature_from_bytes() {
+        let signature = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "bb14e602ef9e3f872e25fad328466b34e6734b7a0fcd58b1eb635447ffae8cb9",
+            ),
+        );
+
+        let rebuilt = ECDSASignature::from_bytes(signature.r(), signature.s()).unwrap();
+        assert_eq!(rebuilt, signature);
+
+        let n = U256BigEndianSerializer::serialize(&ScalarFieldModulus::MODULUS);
+        for (r, s) in [
+            ([0; 32], signature.s()),
+            (signature.r(), [0; 32]),
+            (n, signature.s()),
+            (signature.r(), n),
+        ] {
+            assert_eq!(
+                ECDSASignature::from_bytes(r, s),
+                Err(ParserError::ParseError)
+            );
+        }
+    }
+
+    #[test]
+    fn test_deterministic_signature() {
+        let private_key = PrivateKey::new([
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1,
+        ])
+        .unwrap();
+        let z = Message::new(sha256("Satoshi Nakamoto".as_bytes()));
+
+        let signature = private_key.sign(&z);
+
+        let signature_expected = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+            ),
+        );
+
+        assert_eq!(signature, signature_expected);
+        assert_eq!(private_key.sign(&z), signature);
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        let mut signature = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "bb14e602ef9e3f872e25fad328466b34e6734b7a0fcd58b1eb635447ffae8cb9",
+            ),
+        );
+        assert!(!signature.has_low_s());
+
+        signature.normalize_s();
+
+        let signature_expected = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "44eb19fd1061c078d1da052cd7b994c9d43b916c9f7b4789d46f0a44d087b488",
+            ),
+        );
+        assert!(signature.has_low_s());
+        assert_eq!(signature, signature_expected);
+    }
+
+    #[test]
+    fn test_verify_strict_rejects_high_s() {
+        let z = Message::new(hash256("my message".as_bytes()));
+
+        // public key corresponding to the private key = `hash256("my secret".as_bytes())`
+        let public_key = PublicKey::new(
+            Point::from_affine(
+                BaseFelt::from_hex_unchecked(
+                    "28d003eab2e428d11983f3e97c3fa0addf3b42740df0d211795ffb3be2f6c52",
+                ),
+                BaseFelt::from_hex_unchecked(
+                    "ae987b9ec6ea159c78cb2a937ed89096fb218d9e7594f02b547526d8cd309e2",
+                ),
+            )
+            .unwrap(),
+        );
+
+        let mut signature = ECDSASignature::new(
+            ScalarFelt::from_hex_unchecked(
+                "2b698a0f0a4041b77e63488ad48c23e8e8838dd1fb7520408b121697b782ef22",
+            ),
+            ScalarFelt::from_hex_unchecked(
+                "bb14e602ef9e3f872e25fad328466b34e6734b7a0fcd58b1eb635447ffae8cb9",
+            ),
+        );
+
+        assert!(ECDSA::verify(&z, &signature, &public_key));
+        assert!(!ECDSA::verify_strict(&z, &signature, &public_key));
+
+        signature.normalize_s();
+        assert!(ECDSA::verify_strict(&z, &signature, &public_key));
+    }
+
+    #[test]
+    fn test_sign_recoverable() {
+        let private_key = PrivateKey::new([
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 1,
+        ])
+        .unwrap();
+        let z = Message::new(sha256("Satoshi Nakamoto".as_bytes()));
+
+        let signature = private_key.sign_recoverable(&z);
+
+        let signature_expected = RecoverableSignature::new(
+            ECDSASignature::new(
+                ScalarFelt::from_hex_unchecked(
+                    "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8",
+                ),
+                ScalarFelt::from_hex_unchecked(
+                    "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5",
+                ),
+            ),
+            1,
+        );
+        assert_eq!(signature, signature_expected);
+        assert_eq!(
+            PublicKey::recover(&z, &signature).unwrap(),
+            PublicKey::new(Secp256k1::generator())
+        );
+    }
+
+    #[test]
+    fn test_recover_public_key() {
+        for i in 1..20u8 {
+            let private_key = PrivateKey::new(hash256(&[i])).unwrap();
+            let z = Message::new(hash256(&[i, i]));
+
+            let signature = private_key.sign_recoverable(&z);
+            let public_key = PublicKey::from_private_key(private_key);
+
+            assert_eq!(PublicKey::recover(&z, &signature).unwrap(), public_key);
+            assert!(public_key.verify(&z, signature.signature()));
+        }
+    }
+
+    #[test]
+    fn test_recover_with_wrong_recovery_id() {
+        let private_key = PrivateKey::new(hash256("my secret".as_bytes())).unwrap();
+        let z = Message::new(hash256("my message".as_bytes()));
+
+        let signature = private_key.sign_recoverable(&z);
+        let tampered_signature =
+            RecoverableSignature::new(signature.signature().clone(), signature.recovery_id() ^ 1);
+        let public_key = PublicKey::from_private_key(private_key);
+
+        assert_ne!(
+            PublicKey::recover(&z, &tampered_signature).unwrap(),
+            public_key
+        );
+        assert!(PublicKey::recover(
+            &z,
+            &RecoverableSignature::new(signature.signature().clone(), 4)
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_compact_signature_round_trip() {
+        let private_key = PrivateKey::new(hash256("my secret".as_bytes())).unwrap();
+        let z = Message::new(hash256("my message".as_bytes()));
+        let signature = private_key.sign_recoverable(&z);
+
+        for compressed in [false, true] {
+            let bytes = signature.to_compact(compressed);
+            assert_eq!(
+                bytes[0],
+                27 + signature.recovery_id() + if compressed { 4 } else { 0 }
+            );
+            assert_eq!(bytes[1..33], signature.signature().r());
+            assert_eq!(bytes[33..], signature.signature().s());
+
+            let (parsed_signature, parsed_compressed) =
+                RecoverableSignature::from_compact(&bytes).unwrap();
+            assert_eq!(parsed_signature, signature);
+            assert_eq!(parsed_compressed, compressed);
+        }
+    }
+
+    #[test]
+    fn test_compact_signature_invalid_header() {
+        let mut bytes = [1u8; 65];
+        bytes[0] = 35;
+        assert!(RecoverableSignature::from_compact(&bytes).is_err());
+        bytes[0] = 26;
+        assert!(RecoverableSignature::from_compact(&bytes).is_err());
     }
 }
@@ -1,24 +1,71 @@
+use std::fmt;
+
 use crate::{
-    serializer::{CanSerialize, PublicKeyCompressedSerializer, PublicKeyUncompressedSerializer},
-    PublicKey,
+    interpreter::{decode_number, encode_number},
+    opcode::Opcode,
+    serializer::{
+        CanSerialize, ParserError, PublicKeyCompressedSerializer, PublicKeyUncompressedSerializer,
+        ScriptSerializer,
+    },
+    PublicKey, XOnlyPublicKey,
 };
 
 pub(crate) type TransactionId = [u8; 32];
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub(crate) enum Command {
-    Operation(u8),
+pub enum Command {
+    Operation(Opcode),
+    /// Data pushed with the fewest bytes, see `Opcode::minimal_push`.
     Element(Vec<u8>),
+    /// Data pushed with a given `OP_PUSHBYTES_n` or `OP_PUSHDATAn` opcode, which keeps
+    /// non-minimal pushes found in parsed scripts as they were.
+    Push(Opcode, Vec<u8>),
 }
 
 #[derive(Debug, PartialEq, Eq)]
-pub(crate) struct Script {
+pub struct Script {
     commands: Vec<Command>,
 }
 
-#[derive(Debug)]
+/// Errors building scripts and, from `EvalFalse` on, the reasons the interpreter rejects a
+/// script, mirroring those of Bitcoin Core.
+#[derive(Debug, PartialEq, Eq)]
 pub enum ScriptError {
-    InvalidCommandsError,
+    InvalidCommands,
+    InvalidMultisig,
+    /// A token of a script in ASM that is neither an opcode name, hex data nor a number in range.
+    InvalidAsm(String),
+
+    /// The script finished with an empty stack or a false value on top.
+    EvalFalse,
+    OpReturn,
+    /// The script is longer than 10000 bytes.
+    ScriptSize,
+    /// An element is longer than 520 bytes.
+    PushSize,
+    /// More than 201 non-push operations.
+    OpCount,
+    /// More than 1000 elements in the stack and the alt stack.
+    StackSize,
+    SigCount,
+    PubkeyCount,
+    Verify,
+    EqualVerify,
+    CheckSigVerify,
+    CheckMultisigVerify,
+    NumEqualVerify,
+    /// An invalid or reserved opcode was executed, or a push went past the end of the script.
+    BadOpcode,
+    DisabledOpcode,
+    InvalidStackOperation,
+    InvalidAltstackOperation,
+    UnbalancedConditional,
+    /// A number operand is longer than 4 bytes (5 for the lock time opcodes).
+    NumberOverflow,
+    NegativeLocktime,
+    UnsatisfiedLocktime,
+    /// The scriptSig of a P2SH spend has operations other than pushes.
+    SigPushOnly,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -44,14 +91,30 @@ pub(crate) struct Output {
 }
 
 impl Script {
+    /// Builds a script after checking that every push fits its opcode. Pushes are stored in the
+    /// form parsing their bytes gives, so that scripts with the same bytes compare equal: the
+    /// shortest `Command::Push` becomes a `Command::Element`, and elements written as `OP_1NEGATE`
+    /// or `OP_1` to `OP_16` become those operations.
     pub fn new(commands: Vec<Command>) -> Result<Self, ScriptError> {
         if commands.iter().all(|command| match command {
-            Command::Operation(value) => *value > 77,
-            Command::Element(value) => value.len() < 0x10000,
+            // Data pushes are elements
+            Command::Operation(opcode) => *opcode > Opcode::OP_PUSHDATA4,
+            Command::Element(value) => u32::try_from(value.len()).is_ok(),
+            Command::Push(opcode, value) => match *opcode {
+                Opcode::OP_PUSHDATA1 => value.len() <= 0xff,
+                Opcode::OP_PUSHDATA2 => value.len() <= 0xffff,
+                Opcode::OP_PUSHDATA4 => u32::try_from(value.len()).is_ok(),
+                opcode => {
+                    (Opcode::OP_PUSHBYTES_1..=Opcode::OP_PUSHBYTES_75).contains(&opcode)
+                        && value.len() == u8::from(opcode) as usize
+                }
+            },
         }) {
-            Ok(Self { commands })
+            Ok(Self {
+                commands: commands.into_iter().map(normalize_push).collect(),
+            })
         } else {
-            Err(ScriptError::InvalidCommandsError)
+            Err(ScriptError::InvalidCommands)
         }
     }
 
@@ -63,10 +126,86 @@ impl Script {
             PublicKeyUncompressedSerializer::serialize(public_key).to_vec()
         };
         commands.push(Command::Element(serialized_public_key));
-        commands.push(Command::Operation(0xac));
+        commands.push(Command::Operation(Opcode::OP_CHECKSIG));
         Self { commands }
     }
 
+    /// `OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG`
+    pub fn p2pkh(hash: &[u8; 20]) -> Self {
+        Self {
+            commands: vec![
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
+                Command::Element(hash.to_vec()),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
+            ],
+        }
+    }
+
+    /// `OP_HASH160 <hash> OP_EQUAL`
+    pub fn p2sh(hash: &[u8; 20]) -> Self {
+        Self {
+            commands: vec![
+                Command::Operation(Opcode::OP_HASH160),
+                Command::Element(hash.to_vec()),
+                Command::Operation(Opcode::OP_EQUAL),
+            ],
+        }
+    }
+
+    /// `OP_n <program>` for a witness version `n` between 0 and 16. `OP_0` is the empty push.
+    pub fn witness_program(version: u8, program: &[u8]) -> Self {
+        let version = if version == 0 {
+            Command::Element(vec![])
+        } else {
+            Command::Operation(Opcode::from(0x50 + version))
+        };
+        Self {
+            commands: vec![version, Command::Element(program.to_vec())],
+        }
+    }
+
+    /// `OP_0 <hash>`
+    pub fn p2wpkh(hash: &[u8; 20]) -> Self {
+        Self::witness_program(0, hash)
+    }
+
+    /// `OP_0 <hash>`, where `hash` is the SHA256 of the witness script.
+    pub fn p2wsh(hash: &[u8; 32]) -> Self {
+        Self::witness_program(0, hash)
+    }
+
+    /// `OP_1 <output key>`, where `output_key` is already tweaked.
+    pub fn p2tr(output_key: &XOnlyPublicKey) -> Self {
+        Self::witness_program(1, &output_key.to_bytes())
+    }
+
+    /// `OP_RETURN <data>`, an output that can't be spent.
+    pub fn op_return(data: &[u8]) -> Result<Self, ScriptError> {
+        Self::new(vec![
+            Command::Operation(Opcode::OP_RETURN),
+            Command::Element(data.to_vec()),
+        ])
+    }
+
+    /// `OP_m <public key 1> ... <public key n> OP_n OP_CHECKMULTISIG` with compressed public keys,
+    /// for `1 <= m <= n <= 16`.
+    pub fn multisig(threshold: usize, public_keys: &[PublicKey]) -> Result<Self, ScriptError> {
+        if threshold == 0 || threshold > public_keys.len() || public_keys.len() > 16 {
+            return Err(ScriptError::InvalidMultisig);
+        }
+        let small_integer =
+            |n: usize| Command::Operation(Opcode::from_small_integer(n as u8).unwrap());
+        let mut commands = vec![small_integer(threshold)];
+        commands.extend(public_keys.iter().map(|public_key| {
+            Command::Element(PublicKeyCompressedSerializer::serialize(public_key).to_vec())
+        }));
+        commands.push(small_integer(public_keys.len()));
+        commands.push(Command::Operation(Opcode::OP_CHECKMULTISIG));
+        Ok(Self { commands })
+    }
+
     pub fn empty() -> Self {
         Self { commands: vec![] }
     }
@@ -74,6 +213,281 @@ impl Script {
     pub fn commands(&self) -> &[Command] {
         &self.commands
     }
+
+    /// Parses raw script bytes without a length prefix, as found in a scriptSig or scriptPubKey.
+    /// Pushes that don't use the shortest encoding are kept as `Command::Push`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParserError> {
+        ScriptSerializer::parse_commands(bytes)
+    }
+
+    /// Serializes the script without a length prefix.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        ScriptSerializer::serialize_commands(self)
+    }
+
+    /// `<public key> OP_CHECKSIG` with a compressed or uncompressed public key.
+    pub fn is_p2pk(&self) -> bool {
+        matches!(
+            self.commands.as_slice(),
+            [
+                Command::Element(public_key),
+                Command::Operation(Opcode::OP_CHECKSIG),
+            ] if is_public_key(public_key)
+        )
+    }
+
+    pub fn is_p2pkh(&self) -> bool {
+        matches!(
+            self.commands.as_slice(),
+            [
+                Command::Operation(Opcode::OP_DUP),
+                Command::Operation(Opcode::OP_HASH160),
+                Command::Element(hash),
+                Command::Operation(Opcode::OP_EQUALVERIFY),
+                Command::Operation(Opcode::OP_CHECKSIG),
+            ] if hash.len() == 20
+        )
+    }
+
+    pub fn is_p2sh(&self) -> bool {
+        matches!(
+            self.commands.as_slice(),
+            [
+                Command::Operation(Opcode::OP_HASH160),
+                Command::Element(hash),
+                Command::Operation(Opcode::OP_EQUAL),
+            ] if hash.len() == 20
+        )
+    }
+
+    pub fn is_p2wpkh(&self) -> bool {
+        matches!(self.as_witness_program(), Some((0, program)) if program.len() == 20)
+    }
+
+    pub fn is_p2wsh(&self) -> bool {
+        matches!(self.as_witness_program(), Some((0, program)) if program.len() == 32)
+    }
+
+    pub fn is_p2tr(&self) -> bool {
+        matches!(self.as_witness_program(), Some((1, program)) if program.len() == 32)
+    }
+
+    /// A bare multisig with `1 <= m <= n <= 16` compressed or uncompressed public keys.
+    pub fn is_multisig(&self) -> bool {
+        match self.commands.as_slice() {
+            [
+                Command::Operation(threshold),
+                public_keys @ ..,
+                Command::Operation(count),
+                Command::Operation(Opcode::OP_CHECKMULTISIG),
+            ] => match (threshold.small_integer(), count.small_integer()) {
+                (Some(threshold), Some(count)) => {
+                    1 <= threshold
+                        && threshold <= count
+                        && public_keys.len() == count as usize
+                        && public_keys.iter().all(|command| {
+                            matches!(command, Command::Element(public_key) if is_public_key(public_key))
+                        })
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// `OP_RETURN` followed only by pushes, which carries data instead of coins.
+    pub fn is_op_return(&self) -> bool {
+        match self.commands.split_first() {
+            Some((Command::Operation(Opcode::OP_RETURN), data)) => {
+                data.iter().all(|command| match command {
+                    Command::Element(_) | Command::Push(..) => true,
+                    Command::Operation(opcode) => opcode.is_push(),
+                })
+            }
+            _ => false,
+        }
+    }
+
+    /// The version and program of a witness output: a version push (`OP_0` to `OP_16`) followed
+    /// by a push of 2 to 40 bytes (BIP 141).
+    pub fn as_witness_program(&self) -> Option<(u8, &[u8])> {
+        let [version, Command::Element(program)] = self.commands.as_slice() else {
+            return None;
+        };
+        let version = match version {
+            Command::Element(bytes) => match Opcode::minimal_push(bytes) {
+                Opcode::OP_0 => 0,
+                opcode => opcode.small_integer()?,
+            },
+            Command::Operation(opcode) => opcode.small_integer()?,
+            Command::Push(..) => return None,
+        };
+        (2..=40)
+            .contains(&program.len())
+            .then_some((version, program.as_slice()))
+    }
+
+    /// Whether every command pushes data or a number, as required from the scriptSig of P2SH
+    /// spends.
+    pub fn is_push_only(&self) -> bool {
+        self.commands.iter().all(|command| match command {
+            Command::Element(_) | Command::Push(..) => true,
+            Command::Operation(opcode) => opcode.is_push(),
+        })
+    }
+
+    /// Whether every push uses the shortest encoding of its data, which standard transactions
+    /// require (BIP 62).
+    pub fn has_minimal_pushes(&self) -> bool {
+        self.commands.iter().all(|command| match command {
+            Command::Push(opcode, data) => *opcode == Opcode::minimal_push(data),
+            Command::Element(_) | Command::Operation(_) => true,
+        })
+    }
+
+    /// Parses the ASM format written by `Display`, separated by whitespace: opcode names, hex
+    /// data for pushes, and decimal numbers pushed as script numbers like Bitcoin Core does,
+    /// with `-1` and `1` to `16` read as `OP_1NEGATE` and `OP_1` to `OP_16`. A token made only
+    /// of digits is always a number. A push opcode is followed by its data in hex, or `0` for
+    /// the empty push, and is kept as a `Command::Push` unless it is the shortest push.
+    pub fn from_asm(asm: &str) -> Result<Self, ScriptError> {
+        let parse_data = |token: &str| match token {
+            "0" => Some(vec![]),
+            _ => decode_hex(token),
+        };
+        let mut tokens = asm.split_whitespace();
+        let mut commands = Vec::new();
+        while let Some(token) = tokens.next() {
+            let command = match Opcode::from_name(token) {
+                Some(Opcode::OP_0) => Command::Element(vec![]),
+                Some(opcode) if opcode <= Opcode::OP_PUSHDATA4 => {
+                    let data = tokens
+                        .next()
+                        .and_then(parse_data)
+                        .ok_or_else(|| ScriptError::InvalidAsm(token.to_string()))?;
+                    Command::Push(opcode, data)
+                }
+                Some(opcode) => Command::Operation(opcode),
+                None if is_decimal(token) => {
+                    parse_number(token).ok_or_else(|| ScriptError::InvalidAsm(token.to_string()))?
+                }
+                None => decode_hex(token)
+                    .map(Command::Element)
+                    .ok_or_else(|| ScriptError::InvalidAsm(token.to_string()))?,
+            };
+            commands.push(command);
+        }
+        Self::new(commands)
+    }
+}
+
+/// Bitcoin Core style ASM: `OP_1NEGATE`, `OP_1` to `OP_16` and pushes of numbers of up to 4
+/// bytes in their shortest encoding are written in decimal, and other data in hex. So that the
+/// output reads back as the same script, pushes with an explicit opcode are preceded by it, and
+/// so is data whose hex has only digits and would read as a number.
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, command) in self.commands.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+            match command {
+                Command::Operation(Opcode::OP_1NEGATE) => f.write_str("-1")?,
+                Command::Operation(opcode) => match opcode.small_integer() {
+                    Some(n) => write!(f, "{}", n)?,
+                    None => write!(f, "{}", opcode)?,
+                },
+                Command::Element(bytes) => match script_number(bytes) {
+                    Some(n) => write!(f, "{}", n)?,
+                    None => {
+                        let hex = encode_hex(bytes);
+                        if is_decimal(&hex) {
+                            write!(f, "{} ", Opcode::minimal_push(bytes))?;
+                        }
+                        f.write_str(&hex)?;
+                    }
+                },
+                Command::Push(opcode, bytes) => {
+                    write!(f, "{} ", opcode)?;
+                    write_data(f, bytes)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn normalize_push(command: Command) -> Command {
+    match command {
+        Command::Push(opcode, data) if opcode == Opcode::minimal_push(&data) => {
+            normalize_push(Command::Element(data))
+        }
+        Command::Element(data) => match Opcode::minimal_push(&data) {
+            opcode if opcode == Opcode::OP_1NEGATE || opcode.small_integer().is_some() => {
+                Command::Operation(opcode)
+            }
+            _ => Command::Element(data),
+        },
+        command => command,
+    }
+}
+
+fn write_data(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    if bytes.is_empty() {
+        f.write_str("0")
+    } else {
+        f.write_str(&encode_hex(bytes))
+    }
+}
+
+/// The value of `bytes` if it is a number of at most 4 bytes in its shortest encoding.
+fn script_number(bytes: &[u8]) -> Option<i64> {
+    decode_number(bytes, 4)
+        .ok()
+        .filter(|&value| encode_number(value) == bytes)
+}
+
+/// Reads a decimal token as the command that pushes it. Like Bitcoin Core, numbers up to
+/// `0xffffffff` in absolute value are accepted, which take up to 5 bytes.
+fn parse_number(token: &str) -> Option<Command> {
+    let value: i64 = token.parse().ok()?;
+    if value.unsigned_abs() > 0xffffffff {
+        return None;
+    }
+    Some(match value {
+        -1 => Command::Operation(Opcode::OP_1NEGATE),
+        1..=16 => Command::Operation(Opcode::from_small_integer(value as u8).unwrap()),
+        _ => Command::Element(encode_number(value)),
+    })
+}
+
+/// Digits with an optional leading minus sign.
+fn is_decimal(token: &str) -> bool {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Sizes and prefixes of SEC public keys, without checking that they are on the curve.
+fn is_public_key(bytes: &[u8]) -> bool {
+    match bytes.first() {
+        Some(2 | 3) => bytes.len() == 33,
+        Some(4) => bytes.len() == 65,
+        _ => false,
+    }
 }
 
 impl Input {
@@ -109,14 +523,16 @@ impl Transaction {
 
 #[cfg(test)]
 mod test {
-    
+
+    use lambdaworks_math::unsigned_integer::element::U256;
 
     use crate::{
-        serializer::{CanParse, U256BigEndianSerializer},
-        PublicKey,
+        opcode::Opcode,
+        serializer::{CanParse, ScriptSerializer, U256BigEndianSerializer},
+        PublicKey, XOnlyPublicKey,
     };
 
-    use super::{Command, Script};
+    use super::{decode_hex, Command, ParserError, Script, ScriptError};
 
     #[test]
     fn test_script_constructor_1() {
@@ -126,28 +542,33 @@ mod test {
 
     #[test]
     fn test_script_constructor_2() {
-        let commands = vec![Command::Operation(1)];
+        let commands = vec![Command::Operation(Opcode::OP_PUSHBYTES_1)];
         let _ = Script::new(commands).unwrap_err();
     }
 
     #[test]
     fn test_script_constructor_3() {
-        let commands = vec![Command::Operation(77), Command::Operation(78)];
+        let commands = vec![
+            Command::Operation(Opcode::OP_PUSHDATA2),
+            Command::Operation(Opcode::OP_PUSHDATA4),
+        ];
         let _ = Script::new(commands).unwrap_err();
     }
 
     #[test]
     fn test_script_constructor_4() {
-        let commands = vec![Command::Operation(78)];
+        let commands = vec![Command::Operation(Opcode::OP_PUSHDATA4)];
+        let _ = Script::new(commands).unwrap_err();
+        let commands = vec![Command::Operation(Opcode::OP_1NEGATE)];
         let _ = Script::new(commands).unwrap();
     }
 
     #[test]
     fn test_script_constructor_5() {
         let commands = vec![
-            Command::Operation(80),
+            Command::Operation(Opcode::OP_RESERVED),
             Command::Element(vec![0u8]),
-            Command::Operation(107),
+            Command::Operation(Opcode::OP_TOALTSTACK),
         ];
         let _ = Script::new(commands).unwrap();
     }
@@ -155,9 +576,9 @@ mod test {
     #[test]
     fn test_script_constructor_6() {
         let commands = vec![
-            Command::Operation(80),
+            Command::Operation(Opcode::OP_RESERVED),
             Command::Element(vec![0u8; 0x0ffff]),
-            Command::Operation(107),
+            Command::Operation(Opcode::OP_TOALTSTACK),
         ];
         let _ = Script::new(commands).unwrap();
     }
@@ -165,11 +586,70 @@ mod test {
     #[test]
     fn test_script_constructor_7() {
         let commands = vec![
-            Command::Operation(80),
+            Command::Operation(Opcode::OP_RESERVED),
             Command::Element(vec![0u8; 0x10000]),
-            Command::Operation(107),
+            Command::Operation(Opcode::OP_TOALTSTACK),
         ];
-        let _ = Script::new(commands).unwrap_err();
+        let _ = Script::new(commands).unwrap();
+    }
+
+    #[test]
+    fn test_script_constructor_8() {
+        let valid_pushes = [
+            (Opcode::OP_PUSHBYTES_1, 1),
+            (Opcode::OP_PUSHBYTES_75, 75),
+            (Opcode::OP_PUSHDATA1, 0),
+            (Opcode::OP_PUSHDATA1, 0xff),
+            (Opcode::OP_PUSHDATA2, 0xffff),
+            (Opcode::OP_PUSHDATA4, 0x10000),
+        ];
+        for (opcode, length) in valid_pushes {
+            let _ = Script::new(vec![Command::Push(opcode, vec![0u8; length])]).unwrap();
+        }
+        let invalid_pushes = [
+            (Opcode::OP_0, 0),
+            (Opcode::OP_PUSHBYTES_2, 1),
+            (Opcode::OP_PUSHDATA1, 0x100),
+            (Opcode::OP_PUSHDATA2, 0x10000),
+            (Opcode::OP_1, 1),
+            (Opcode::OP_DUP, 1),
+        ];
+        for (opcode, length) in invalid_pushes {
+            let _ = Script::new(vec![Command::Push(opcode, vec![0u8; length])]).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn test_script_constructor_normalizes_pushes() {
+        let hash = [0xab; 20];
+        let script = Script::new(vec![
+            Command::Operation(Opcode::OP_HASH160),
+            Command::Push(Opcode::OP_PUSHBYTES_20, hash.to_vec()),
+            Command::Operation(Opcode::OP_EQUAL),
+        ])
+        .unwrap();
+        assert!(script.is_p2sh());
+        assert_eq!(script, Script::p2sh(&hash));
+
+        let script = Script::new(vec![
+            Command::Element(vec![5]),
+            Command::Element(vec![0x81]),
+            Command::Push(Opcode::OP_PUSHBYTES_1, vec![5]),
+        ])
+        .unwrap();
+        assert_eq!(
+            script.commands(),
+            &[
+                Command::Operation(Opcode::OP_5),
+                Command::Operation(Opcode::OP_1NEGATE),
+                Command::Push(Opcode::OP_PUSHBYTES_1, vec![5]),
+            ]
+        );
+        assert_eq!(
+            ScriptSerializer::parse_commands(&ScriptSerializer::serialize_commands(&script))
+                .unwrap(),
+            script
+        );
     }
 
     #[test]
@@ -189,7 +669,7 @@ mod test {
                 3, 163, 75, 153, 242, 44, 121, 12, 78, 54, 178, 179, 194, 195, 90, 54, 219, 6, 34,
                 110, 65, 198, 146, 252, 130, 184, 181, 106, 193, 197, 64, 197, 189,
             ]),
-            Command::Operation(0xac),
+            Command::Operation(Opcode::OP_CHECKSIG),
         ])
         .unwrap();
 
@@ -217,11 +697,347 @@ mod test {
                 82, 53, 160, 250, 135, 34, 71, 108, 119, 9, 192, 37, 89, 227, 170, 115, 170, 3,
                 145, 139, 162, 212, 146, 238, 167, 90, 190, 162, 53,
             ]),
-            Command::Operation(0xac),
+            Command::Operation(Opcode::OP_CHECKSIG),
         ])
         .unwrap();
 
         let script = Script::p2pk(&public_key, false);
         assert_eq!(script, expected_script);
     }
+
+    #[test]
+    fn test_multisig_invalid_threshold() {
+        let public_keys: Vec<PublicKey> = (1..=17)
+            .map(|i| PublicKey::from_u256(U256::from_u64(i)))
+            .collect();
+        assert!(Script::multisig(2, &public_keys[..3]).is_ok());
+        assert!(Script::multisig(16, &public_keys[..16]).is_ok());
+        assert_eq!(
+            Script::multisig(0, &public_keys[..3]),
+            Err(ScriptError::InvalidMultisig)
+        );
+        assert_eq!(
+            Script::multisig(4, &public_keys[..3]),
+            Err(ScriptError::InvalidMultisig)
+        );
+        assert_eq!(
+            Script::multisig(1, &public_keys),
+            Err(ScriptError::InvalidMultisig)
+        );
+    }
+
+    #[test]
+    fn test_asm_round_trip() {
+        let vectors = [
+            (
+                "76a91489abcdefabbaabbaabbaabbaabbaabbaabbaabba88ac",
+                "OP_DUP OP_HASH160 89abcdefabbaabbaabbaabbaabbaabbaabbaabba OP_EQUALVERIFY OP_CHECKSIG",
+            ),
+            (
+                "0014751e76e8199196d454941c45d1b3a323f1433bd6",
+                "0 751e76e8199196d454941c45d1b3a323f1433bd6",
+            ),
+            (
+                "6a0b68656c6c6f20776f726c64",
+                "OP_RETURN 68656c6c6f20776f726c64",
+            ),
+            ("51b175", "1 OP_CHECKLOCKTIMEVERIFY OP_DROP"),
+            ("", ""),
+            // As printed by decodescript in Bitcoin Core
+            ("5100", "1 0"),
+            (
+                "632103b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2ad670320a107b175682103b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2ac",
+                "OP_IF 03b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2 OP_CHECKSIGVERIFY OP_ELSE 500000 OP_CHECKLOCKTIMEVERIFY OP_DROP OP_ENDIF 03b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2 OP_CHECKSIG",
+            ),
+            (
+                "522103b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb22103b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb22103b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb253ae",
+                "2 03b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2 03b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2 03b0da749730dc9b4b1f4a14d6902877a92541f5368778853d9c4a0cb7802dcfb2 3 OP_CHECKMULTISIG",
+            ),
+            ("029000b2", "144 OP_CHECKSEQUENCEVERIFY"),
+            (
+                "5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+                "1 a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+            ),
+        ];
+        for (hex, asm) in vectors {
+            let bytecode = decode_hex(hex).unwrap();
+            let script = ScriptSerializer::parse_commands(&bytecode).unwrap();
+            assert_eq!(script.to_string(), asm);
+            let parsed = Script::from_asm(asm).unwrap();
+            assert_eq!(parsed, script);
+            assert_eq!(ScriptSerializer::serialize_commands(&parsed), bytecode);
+        }
+    }
+
+    #[test]
+    fn test_asm_numbers() {
+        let vectors = [
+            ("1000", "02e803"),
+            ("-1000", "02e883"),
+            ("-1", "4f"),
+            ("16", "60"),
+            ("17", "0111"),
+            ("-0", "00"),
+            ("0500", "02f401"),
+            ("2147483647", "04ffffff7f"),
+            ("4294967295", "05ffffffff00"),
+        ];
+        for (asm, hex) in vectors {
+            let script = Script::from_asm(asm).unwrap();
+            assert_eq!(
+                ScriptSerializer::serialize_commands(&script),
+                decode_hex(hex).unwrap()
+            );
+        }
+        assert_eq!(
+            Script::from_asm("4294967296"),
+            Err(ScriptError::InvalidAsm("4294967296".to_string()))
+        );
+        assert_eq!(
+            Script::from_asm("-"),
+            Err(ScriptError::InvalidAsm("-".to_string()))
+        );
+
+        // Non-minimal numbers and numbers over 4 bytes are written in hex, with an explicit
+        // opcode if the hex would read as a decimal number
+        let vectors = [
+            ("020500", "OP_PUSHBYTES_2 0500"),
+            ("0180", "OP_PUSHBYTES_1 80"),
+            ("05ffffffff00", "ffffffff00"),
+            ("0112", "18"),
+        ];
+        for (hex, asm) in vectors {
+            let bytecode = decode_hex(hex).unwrap();
+            let script = ScriptSerializer::parse_commands(&bytecode).unwrap();
+            assert_eq!(script.to_string(), asm);
+            assert_eq!(Script::from_asm(asm).unwrap(), script);
+        }
+    }
+
+    #[test]
+    fn test_from_asm() {
+        assert_eq!(
+            Script::from_asm("  OP_TRUE\n OP_NOP2 OP_0 OP_FALSE ").unwrap(),
+            Script::new(vec![
+                Command::Operation(Opcode::OP_1),
+                Command::Operation(Opcode::OP_CHECKLOCKTIMEVERIFY),
+                Command::Element(vec![]),
+                Command::Element(vec![]),
+            ])
+            .unwrap()
+        );
+        assert_eq!(
+            Script::from_asm("OP_DUP OP_FOO"),
+            Err(ScriptError::InvalidAsm("OP_FOO".to_string()))
+        );
+        assert_eq!(
+            Script::from_asm("abc"),
+            Err(ScriptError::InvalidAsm("abc".to_string()))
+        );
+        assert_eq!(
+            Script::from_asm("+1"),
+            Err(ScriptError::InvalidAsm("+1".to_string()))
+        );
+        assert_eq!(
+            Script::from_asm("OP_PUSHDATA1 OP_DUP"),
+            Err(ScriptError::InvalidAsm("OP_PUSHDATA1".to_string()))
+        );
+    }
+
+    fn script_types(script: &Script) -> Vec<&'static str> {
+        [
+            ("p2pk", script.is_p2pk()),
+            ("p2pkh", script.is_p2pkh()),
+            ("p2sh", script.is_p2sh()),
+            ("p2wpkh", script.is_p2wpkh()),
+            ("p2wsh", script.is_p2wsh()),
+            ("p2tr", script.is_p2tr()),
+            ("multisig", script.is_multisig()),
+            ("op_return", script.is_op_return()),
+        ]
+        .into_iter()
+        .filter_map(|(name, matches)| matches.then_some(name))
+        .collect()
+    }
+
+    #[test]
+    fn test_script_templates() {
+        let public_keys: Vec<PublicKey> = (1..=3)
+            .map(|i| PublicKey::from_u256(U256::from_u64(i)))
+            .collect();
+        let hash: [u8; 20] = decode_hex("751e76e8199196d454941c45d1b3a323f1433bd6")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let script_hash: [u8; 32] =
+            decode_hex("1863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        // Output key of the first BIP 86 test vector
+        let output_key = XOnlyPublicKey::from_bytes(
+            &decode_hex("a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        )
+        .unwrap();
+
+        let vectors = [
+            (Script::p2pk(&public_keys[0], false), "p2pk"),
+            (Script::p2pkh(&hash), "p2pkh"),
+            (Script::p2sh(&hash), "p2sh"),
+            (Script::p2wpkh(&hash), "p2wpkh"),
+            (Script::p2wsh(&script_hash), "p2wsh"),
+            (Script::p2tr(&output_key), "p2tr"),
+            (Script::multisig(2, &public_keys).unwrap(), "multisig"),
+            (Script::op_return(b"hello world").unwrap(), "op_return"),
+        ];
+        for (script, script_type) in vectors {
+            assert_eq!(script_types(&script), vec![script_type]);
+        }
+
+        assert_eq!(
+            ScriptSerializer::serialize_commands(&Script::p2wpkh(&hash)),
+            decode_hex("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+        );
+        assert_eq!(
+            ScriptSerializer::serialize_commands(&Script::p2tr(&output_key)),
+            decode_hex("5120a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_script_matchers() {
+        let script = Script::from_asm("OP_16 751e").unwrap();
+        assert_eq!(script.as_witness_program(), Some((16, &[0x75, 0x1e][..])));
+        assert_eq!(script_types(&script), Vec::<&str>::new());
+        assert_eq!(
+            Script::from_asm("OP_1 75").unwrap().as_witness_program(),
+            None
+        );
+        assert_eq!(
+            Script::from_asm(&format!("0 {}", "00".repeat(41)))
+                .unwrap()
+                .as_witness_program(),
+            None
+        );
+        assert_eq!(
+            Script::from_asm("OP_NOP 751e")
+                .unwrap()
+                .as_witness_program(),
+            None
+        );
+        // Witness version 0 with a program of neither 20 nor 32 bytes
+        assert_eq!(
+            script_types(&Script::from_asm("0 751e").unwrap()),
+            Vec::<&str>::new()
+        );
+
+        assert!(Script::from_asm("OP_RETURN").unwrap().is_op_return());
+        assert!(Script::from_asm("OP_RETURN 0 OP_16 ff")
+            .unwrap()
+            .is_op_return());
+        assert!(!Script::from_asm("OP_RETURN OP_DUP").unwrap().is_op_return());
+
+        let public_key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        assert!(
+            Script::from_asm(&format!("OP_1 {public_key} OP_1 OP_CHECKMULTISIG"))
+                .unwrap()
+                .is_multisig()
+        );
+        assert!(
+            !Script::from_asm(&format!("OP_2 {public_key} OP_1 OP_CHECKMULTISIG"))
+                .unwrap()
+                .is_multisig()
+        );
+        assert!(
+            !Script::from_asm(&format!("OP_1 {public_key} OP_2 OP_CHECKMULTISIG"))
+                .unwrap()
+                .is_multisig()
+        );
+        assert!(!Script::from_asm("OP_1 0579be OP_1 OP_CHECKMULTISIG")
+            .unwrap()
+            .is_multisig());
+        assert!(!Script::from_asm(&format!("{public_key}00 OP_CHECKSIG"))
+            .unwrap()
+            .is_p2pk());
+    }
+
+    #[test]
+    fn test_push_checks() {
+        let minimal = Script::from_asm("0 05 81 0500 OP_16").unwrap();
+        assert!(minimal.is_push_only());
+        assert!(minimal.has_minimal_pushes());
+
+        let non_minimal = Script::from_asm("OP_PUSHBYTES_1 05 OP_1").unwrap();
+        assert!(non_minimal.is_push_only());
+        assert!(!non_minimal.has_minimal_pushes());
+        assert!(!Script::from_asm("OP_PUSHDATA1 0a0b")
+            .unwrap()
+            .has_minimal_pushes());
+        assert!(!Script::from_asm("OP_PUSHDATA1 0")
+            .unwrap()
+            .has_minimal_pushes());
+        assert!(
+            Script::from_asm(&format!("OP_PUSHDATA1 {}", "00".repeat(76)))
+                .unwrap()
+                .has_minimal_pushes()
+        );
+
+        assert!(!Script::from_asm("05 OP_NOP").unwrap().is_push_only());
+        assert!(Script::from_asm("OP_RESERVED OP_1NEGATE")
+            .unwrap()
+            .is_push_only());
+    }
+
+    #[test]
+    fn test_asm_explicit_pushes() {
+        let bytecode = decode_hex("0105004c020a0b4e0100000081").unwrap();
+        let script = ScriptSerializer::parse_commands(&bytecode).unwrap();
+        assert_eq!(
+            script.to_string(),
+            "OP_PUSHBYTES_1 05 0 OP_PUSHDATA1 0a0b OP_PUSHDATA4 81"
+        );
+        assert_eq!(Script::from_asm(&script.to_string()).unwrap(), script);
+        assert_eq!(
+            Script::from_asm("OP_PUSHDATA1"),
+            Err(ScriptError::InvalidAsm("OP_PUSHDATA1".to_string()))
+        );
+        assert_eq!(
+            Script::from_asm("OP_PUSHBYTES_2 05"),
+            Err(ScriptError::InvalidCommands)
+        );
+    }
+
+    #[test]
+    fn test_script_bytes_round_trip() {
+        // scriptPubKey of a P2PKH output
+        let script_pubkey =
+            decode_hex("76a914bc3b654dca7e56b04dca18f2566cdaf02e8d9ada88ac").unwrap();
+        let script = Script::from_bytes(&script_pubkey).unwrap();
+        assert!(script.is_p2pkh());
+        assert_eq!(script.to_bytes(), script_pubkey);
+
+        // scriptSig of a P2PKH spend: a signature and a compressed public key
+        let script_sig = decode_hex(
+            "483045022100ed81ff192e75a3fd2304004dcadb746fa5e24c5031ccfcf21320b0277457c98f02207a986d955c6e0cb35d446a89d3f56100f4d7f67801c31967743a9c8e10615bed01210349fc4e631e3624a545de3f89f5d8684c7b8138bd94bdd531d2e213bf016b278a",
+        )
+        .unwrap();
+        let script = Script::from_bytes(&script_sig).unwrap();
+        assert!(script.is_push_only());
+        assert_eq!(script.commands().len(), 2);
+        assert_eq!(script.to_bytes(), script_sig);
+
+        // Non-minimal pushes survive the round trip
+        let bytecode = decode_hex("4c0105").unwrap();
+        assert_eq!(Script::from_bytes(&bytecode).unwrap().to_bytes(), bytecode);
+
+        assert_eq!(Script::from_bytes(&[]).unwrap(), Script::empty());
+        assert_eq!(
+            Script::from_bytes(&decode_hex("0205").unwrap()),
+            Err(ParserError::ParseError)
+        );
+    }
 }